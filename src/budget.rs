@@ -0,0 +1,152 @@
+use crate::config::{Budget, Config, Rule};
+use crate::todo_analyzer::{TodoItem, TodoWarning};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counts {
+    todos: usize,
+    overdue: usize,
+    invalid: usize,
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.todos += other.todos;
+        self.overdue += other.overdue;
+        self.invalid += other.invalid;
+    }
+}
+
+/// How much of a single budget limit is used.
+#[derive(Debug)]
+pub struct BudgetUsage {
+    pub scope: String,
+    pub metric: &'static str,
+    pub used: usize,
+    pub limit: usize,
+}
+
+impl BudgetUsage {
+    pub fn is_exceeded(&self) -> bool {
+        self.used > self.limit
+    }
+}
+
+/// Counts TODOs per rule, directory and owner to compare them against the configured budgets.
+#[derive(Debug, Default)]
+pub struct BudgetTracker {
+    rules: BTreeMap<usize, Counts>,
+    directories: BTreeMap<String, Counts>,
+    owners: BTreeMap<String, Counts>,
+}
+
+impl BudgetTracker {
+    pub fn record(
+        &mut self,
+        config: &Config,
+        rule_index: usize,
+        file_path: &str,
+        todos: &[TodoItem],
+        warnings: &[TodoWarning],
+    ) {
+        let mut file_counts = Counts::default();
+        let mut owner_counts: BTreeMap<&str, Counts> = BTreeMap::new();
+
+        for todo in todos {
+            file_counts.todos += 1;
+            if let Some(owner) = &todo.owner {
+                owner_counts.entry(owner).or_default().todos += 1;
+            }
+        }
+
+        for warning in warnings {
+            let (owner, counts) = match warning {
+                TodoWarning::Overdue { owner, .. } => (owner, &mut file_counts.overdue),
+                TodoWarning::InvalidFormat { .. }
+                | TodoWarning::Stale { .. }
+                | TodoWarning::UnknownDueDate { .. } => (&None, &mut file_counts.invalid),
+                _ => continue,
+            };
+            *counts += 1;
+            if let Some(owner) = owner {
+                owner_counts.entry(owner).or_default().overdue += 1;
+            }
+        }
+
+        self.rules.entry(rule_index).or_default().add(file_counts);
+
+        for directory in config.budgets.directories.keys() {
+            if Path::new(file_path).starts_with(directory) {
+                self.directories
+                    .entry(directory.clone())
+                    .or_default()
+                    .add(file_counts);
+            }
+        }
+
+        for (owner, counts) in owner_counts {
+            self.owners
+                .entry(owner.to_string())
+                .or_default()
+                .add(counts);
+        }
+    }
+
+    pub fn report(&self, config: &Config) -> Vec<BudgetUsage> {
+        let mut usages = Vec::new();
+
+        for (rule_index, rule) in config.rules.iter().enumerate() {
+            if let Some(budget) = &rule.budget {
+                let counts = self.rules.get(&rule_index).copied().unwrap_or_default();
+                add_usages(&mut usages, rule_scope(rule_index, rule), budget, counts);
+            }
+        }
+
+        for (directory, budget) in &config.budgets.directories {
+            let counts = self.directories.get(directory).copied().unwrap_or_default();
+            add_usages(
+                &mut usages,
+                format!("directory {}", directory),
+                budget,
+                counts,
+            );
+        }
+
+        for (owner, budget) in &config.budgets.owners {
+            let counts = self.owners.get(owner).copied().unwrap_or_default();
+            add_usages(&mut usages, format!("owner @{}", owner), budget, counts);
+        }
+
+        usages
+    }
+}
+
+pub fn rule_scope(rule_index: usize, rule: &Rule) -> String {
+    let paths: Vec<String> = rule.paths.iter().map(|p| p.display().to_string()).collect();
+    format!(
+        "rule #{} ({}: {})",
+        rule_index + 1,
+        rule.language,
+        paths.join(", ")
+    )
+}
+
+fn add_usages(usages: &mut Vec<BudgetUsage>, scope: String, budget: &Budget, counts: Counts) {
+    let limits = [
+        ("max_todos", budget.max_todos, counts.todos),
+        ("max_overdue", budget.max_overdue, counts.overdue),
+        ("max_invalid", budget.max_invalid, counts.invalid),
+    ];
+
+    for (metric, limit, used) in limits {
+        if let Some(limit) = limit {
+            usages.push(BudgetUsage {
+                scope: scope.clone(),
+                metric,
+                used,
+                limit,
+            });
+        }
+    }
+}
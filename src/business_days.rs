@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// The `[business_days]` section, which counts the due-soon window in business days,
+/// skipping weekends and holidays.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BusinessDays {
+    /// The days of the week that aren't business days, like `sat` or `sunday`.
+    #[serde(default = "default_weekend")]
+    pub weekend: Vec<String>,
+    /// Holidays as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub holidays: Vec<String>,
+    /// An `.ics` or CSV file with more holidays, relative to the config file.
+    pub holidays_file: Option<String>,
+    #[serde(skip)]
+    weekend_days: Vec<Weekday>,
+    #[serde(skip)]
+    holiday_dates: BTreeSet<NaiveDate>,
+}
+
+fn default_weekend() -> Vec<String> {
+    vec!["sat".to_string(), "sun".to_string()]
+}
+
+impl BusinessDays {
+    /// Parses the weekend days and holidays, and reads the holidays file relative to
+    /// `base_dir`.
+    pub fn load(&mut self, base_dir: &Path) -> Result<()> {
+        self.weekend_days = self
+            .weekend
+            .iter()
+            .map(|day| parse_weekend_day(day))
+            .collect::<Result<_>>()?;
+        self.holiday_dates = self
+            .holidays
+            .iter()
+            .map(|holiday| parse_holiday(holiday))
+            .collect::<Result<_>>()?;
+        if let Some(file) = &self.holidays_file {
+            self.holiday_dates
+                .extend(read_holidays(&base_dir.join(file))?);
+        }
+        Ok(())
+    }
+
+    fn is_weekend(&self, date: NaiveDate) -> bool {
+        self.weekend_days.contains(&date.weekday())
+    }
+
+    /// The number of business days after `from` up to and including `to`, so a TODO due on
+    /// Monday is due in one business day on Friday. Full weeks are counted at once, so that
+    /// due dates years away take no longer than the ones next week.
+    pub fn between(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        let days = (to - from).num_days();
+        if days <= 0 {
+            return 0;
+        }
+        let workdays = |first: NaiveDate, count: i64| {
+            first
+                .iter_days()
+                .take(count as usize)
+                .filter(|date| !self.is_weekend(*date))
+                .count() as i64
+        };
+        let (full_weeks, rest) = (days / 7, days % 7);
+        let Some(first) = from.succ_opt() else {
+            return 0;
+        };
+        let after_full_weeks = first
+            .checked_add_days(Days::new(full_weeks as u64 * 7))
+            .map_or(0, |after_full_weeks| workdays(after_full_weeks, rest));
+        let holidays = self
+            .holiday_dates
+            .range(first..=to)
+            .filter(|date| !self.is_weekend(**date))
+            .count() as i64;
+        full_weeks * workdays(first, 7) + after_full_weeks - holidays
+    }
+}
+
+pub fn parse_weekend_day(day: &str) -> Result<Weekday> {
+    day.parse().map_err(|_| {
+        anyhow!(
+            "Unknown weekend day \"{}\", use mon, tue, wed, thu, fri, sat or sun",
+            day
+        )
+    })
+}
+
+pub fn parse_holiday(holiday: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(holiday, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Holiday \"{}\" must be a date like 2024-12-25", holiday))
+}
+
+/// Reads the holidays of an iCalendar file from the start dates of its events, or of a CSV
+/// file from its `date` column.
+pub fn read_holidays(path: &Path) -> Result<Vec<NaiveDate>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read holidays {}: {}", path.display(), e))?;
+    let holidays = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ics") => parse_ics(&content),
+        Some("csv") => parse_csv(&content),
+        _ => Err(anyhow!("Use an .ics or .csv file")),
+    };
+    holidays.map_err(|e| anyhow!("Invalid holidays {}: {}", path.display(), e))
+}
+
+fn parse_ics(content: &str) -> Result<Vec<NaiveDate>> {
+    content
+        .lines()
+        .filter(|line| line.starts_with("DTSTART"))
+        .map(|line| {
+            // DTSTART;VALUE=DATE:20241225, or a date and time like 20241225T000000Z
+            let value = line.split_once(':').map_or("", |(_, value)| value.trim());
+            value
+                .get(..8)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                .ok_or_else(|| anyhow!("Can't read the date of \"{}\"", line.trim()))
+        })
+        .collect()
+}
+
+fn parse_csv(content: &str) -> Result<Vec<NaiveDate>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let date_column = lines
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .position(|column| column.trim() == "date")
+        .ok_or_else(|| anyhow!("Missing date column in the header"))?;
+
+    lines
+        .enumerate()
+        .map(|(index, line)| {
+            line.split(',')
+                .nth(date_column)
+                .and_then(|date| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok())
+                .ok_or_else(|| anyhow!("Row {} must have a date like 2024-12-25", index + 2))
+        })
+        .collect()
+}
@@ -0,0 +1,137 @@
+use crate::validate::{did_you_mean, suggest};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The due dates that name a sprint or a milestone of the calendar, e.g. `sprint-42` or
+/// `milestone:v3.0`.
+pub const CALENDAR_NAME_PATTERN: &str = r"sprint-[\w.-]+|milestone:[\w.-]+";
+
+/// The end dates of sprints and milestones, read from the file of the `calendar` parameter.
+#[derive(Debug, Default)]
+pub struct Calendar {
+    /// The file as configured, for the warnings about unknown names.
+    path: Option<String>,
+    /// The end date of each name, like `sprint-42` or `milestone:v3.0`.
+    end_dates: BTreeMap<String, NaiveDate>,
+}
+
+impl Calendar {
+    /// Reads a TOML file with `[sprints]` and `[milestones]` tables, or a CSV file with
+    /// `name` and `end` columns. Relative paths are relative to `base_dir`.
+    pub fn from_file(base_dir: &Path, file: &str) -> Result<Self> {
+        let path = base_dir.join(file);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read calendar {}: {}", path.display(), e))?;
+        let end_dates = if path.extension().is_some_and(|ext| ext == "csv") {
+            parse_csv(&content)
+        } else {
+            parse_toml(&content)
+        }
+        .map_err(|e| anyhow!("Invalid calendar {}: {}", path.display(), e))?;
+
+        Ok(Calendar {
+            path: Some(file.to_string()),
+            end_dates,
+        })
+    }
+
+    /// The end date of a sprint or milestone.
+    pub fn resolve(&self, name: &str) -> Option<NaiveDate> {
+        self.end_dates.get(name).copied()
+    }
+
+    /// Why a name can't be resolved, suggesting a similar one of the calendar.
+    pub fn unknown_name_message(&self, name: &str) -> String {
+        match &self.path {
+            Some(path) => format!(
+                "{} isn't in the calendar {}{}",
+                name,
+                path,
+                did_you_mean(suggest(name, self.end_dates.keys().map(String::as_str)))
+            ),
+            None => format!("{} needs a calendar in [parameters]", name),
+        }
+    }
+}
+
+/// Whether a due date names a sprint or a milestone instead of a date.
+pub fn is_calendar_name(due_date: &str) -> bool {
+    due_date.starts_with("sprint-") || due_date.starts_with("milestone:")
+}
+
+fn parse_toml(content: &str) -> Result<BTreeMap<String, NaiveDate>> {
+    let table: toml::Table = toml::from_str(content)?;
+    let mut end_dates = BTreeMap::new();
+    for (section, entries) in &table {
+        let prefix = match section.as_str() {
+            "sprints" => "sprint-",
+            "milestones" => "milestone:",
+            _ => anyhow::bail!("Unknown table [{}], use [sprints] or [milestones]", section),
+        };
+        let entries = entries
+            .as_table()
+            .ok_or_else(|| anyhow!("[{}] must be a table", section))?;
+        for (name, end_date) in entries {
+            let end_date = match end_date {
+                toml::Value::Datetime(datetime) if datetime.time.is_none() => {
+                    datetime.date.and_then(|date| {
+                        NaiveDate::from_ymd_opt(
+                            date.year as i32,
+                            date.month as u32,
+                            date.day as u32,
+                        )
+                    })
+                }
+                toml::Value::String(end_date) => parse_end_date(end_date),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow!("{}.{} must be a date like 2024-03-15", section, name))?;
+            end_dates.insert(format!("{}{}", prefix, name), end_date);
+        }
+    }
+    Ok(end_dates)
+}
+
+fn parse_csv(content: &str) -> Result<BTreeMap<String, NaiveDate>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| *column == name)
+            .ok_or_else(|| anyhow!("Missing {} column in the header", name))
+    };
+    let (name_column, end_column) = (column("name")?, column("end")?);
+
+    let mut end_dates = BTreeMap::new();
+    for (index, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let row = index + 2;
+        let name = fields
+            .get(name_column)
+            .filter(|name| is_calendar_name(name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Row {} must have a name like sprint-42 or milestone:v3.0",
+                    row
+                )
+            })?;
+        let end_date = fields
+            .get(end_column)
+            .and_then(|end_date| parse_end_date(end_date))
+            .ok_or_else(|| anyhow!("Row {} must have an end date like 2024-03-15", row))?;
+        end_dates.insert(name.to_string(), end_date);
+    }
+    Ok(end_dates)
+}
+
+fn parse_end_date(end_date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(end_date, "%Y-%m-%d").ok()
+}
@@ -1,9 +1,28 @@
-use clap::Parser;
+use crate::config::Severity;
+use crate::dates::Deadlines;
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate};
+use clap::{Args, Parser, Subcommand};
+use std::fmt::Write;
+
+/// The values of `--type`, along with the warning type they select.
+pub const WARNING_TYPES: [(&str, &str); 6] = [
+    ("overdue", "Overdue"),
+    ("due-soon", "DueSoon"),
+    ("stale", "Stale"),
+    ("invalid-format", "InvalidFormat"),
+    ("policy-violation", "PolicyViolation"),
+    ("unknown-due-date", "UnknownDueDate"),
+];
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
-    pub config_file_path: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// The config file, searched for upward from the current directory if not given
+    pub config_file_path: Option<String>,
 
     #[clap(long, conflicts_with = "check_due_only")]
     pub check_format_only: bool,
@@ -11,8 +30,115 @@ pub struct Cli {
     #[clap(long, conflicts_with = "check_format_only")]
     pub check_due_only: bool,
 
-    #[clap(long, default_value = "0")]
-    pub due_in: u32,
+    /// Overrides the due_in setting of the config file [default: 0]
+    #[clap(long)]
+    pub due_in: Option<u32>,
+
+    /// Overrides the max_comment_length setting of the config file [default: 100]
+    #[clap(long)]
+    pub max_comment_length: Option<usize>,
+
+    #[clap(long, default_value = "table")]
+    pub format: String,
+
+    /// How to write due dates in the output, as a chrono format like %d.%m.%Y
+    #[clap(long, default_value = "%Y-%m-%d")]
+    pub display_date_format: String,
+
+    /// Only report TODOs of this owner, can be given multiple times
+    #[clap(long)]
+    pub owner: Vec<String>,
+
+    /// Only report warnings of these types: overdue, due-soon, stale, invalid-format,
+    /// policy-violation or unknown-due-date
+    #[clap(long = "type", value_delimiter = ',')]
+    pub types: Vec<String>,
+
+    /// Only report TODOs in files under this path, relative to the config file
+    #[clap(long)]
+    pub path_prefix: Vec<String>,
+
+    /// Only report your own TODOs, going by the git user.email and user.name
+    #[clap(long)]
+    pub mine: bool,
+
+    /// Only report warnings matching this expression, e.g.
+    /// 'type == "Overdue" && owner in ["alice", "bob"] && path ~ "^src/api"'
+    #[clap(long)]
+    pub filter: Vec<String>,
+
+    /// Only report warnings matching the filter of this view of the config file
+    #[clap(long)]
+    pub view: Vec<String>,
+
+    /// How to split the output into tables: file, owner, type or none
+    #[clap(long, default_value = "file")]
+    pub group_by: String,
+
+    /// The order of the TODOs within each group: line, due, owner or type
+    #[clap(long, default_value = "line")]
+    pub sort_by: String,
+
+    #[clap(long)]
+    pub no_tty: bool,
+
+    #[clap(long)]
+    pub exit_zero: bool,
+
+    /// The lowest severity that makes the run fail: error, warning or never
+    #[clap(long, default_value = "warning")]
+    pub fail_on: String,
+
+    /// Evaluate deadlines and ages as of this date (YYYY-MM-DD) instead of the current date
+    #[clap(long)]
+    pub today: Option<NaiveDate>,
+
+    /// Rewrite TODOs with an invalid format into the `TODO: YYYY-MM-DD @owner text` form
+    #[clap(long)]
+    pub fix: bool,
+
+    /// Print the changes of --fix as a unified diff instead of writing them
+    #[clap(long, requires = "fix")]
+    pub dry_run: bool,
+
+    /// The owner for --fix when git blame has no author for the TODO line
+    #[clap(long, requires = "fix")]
+    pub default_owner: Option<String>,
+
+    /// The due date for --fix, as YYYY-MM-DD or relative to today like +90d or +2w
+    #[clap(long, default_value = "+90d")]
+    pub default_due: String,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Report TODOs added, resolved and modified between two git revisions
+    Diff(DiffArgs),
+    /// Check the config file for unknown keys, unknown languages, missing paths and other
+    /// mistakes
+    ValidateConfig(ValidateConfigArgs),
+    /// Create a todo-reminder.toml with a rule for each language found in the current
+    /// directory
+    Init(InitArgs),
+    /// Change the owner of TODOs, e.g. when someone leaves the team
+    Reassign(ReassignArgs),
+    /// Move the due date of TODOs, e.g. when a release slips
+    Postpone(PostponeArgs),
+    /// Show how a comment is read, and what keeps it from having a valid format
+    Explain(ExplainArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The revision to compare from, e.g. a tag of the previous release
+    pub from: String,
+
+    /// The revision to compare to, e.g. HEAD
+    pub to: String,
+
+    /// The config file, searched for upward from the current directory if not given
+    #[clap(long = "config")]
+    pub config_file_path: Option<String>,
 
     #[clap(long, default_value = "100")]
     pub max_comment_length: usize,
@@ -20,19 +146,258 @@ pub struct Cli {
     #[clap(long, default_value = "table")]
     pub format: String,
 
+    /// How to write due dates in the output, as a chrono format like %d.%m.%Y
+    #[clap(long, default_value = "%Y-%m-%d")]
+    pub display_date_format: String,
+
     #[clap(long)]
     pub no_tty: bool,
+}
 
+#[derive(Args, Debug)]
+pub struct ValidateConfigArgs {
+    /// The config file, searched for upward from the current directory if not given
+    #[clap(long = "config")]
+    pub config_file_path: Option<String>,
+
+    /// Print the JSON Schema of the config file instead, for editor autocompletion
     #[clap(long)]
-    pub exit_zero: bool,
+    pub schema: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Overwrite an existing todo-reminder.toml
+    #[clap(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// The comment, e.g. "// TODO 2024/05/01 alice fix the parser"
+    pub comment: String,
+
+    /// The config file, searched for upward from the current directory if not given. The
+    /// defaults are used if none is found
+    #[clap(long = "config")]
+    pub config_file_path: Option<String>,
+
+    /// The words that mark a TODO, overriding the config file, which defaults to TODO and
+    /// FIXME
+    #[clap(long = "keyword", value_delimiter = ',')]
+    pub keywords: Vec<String>,
+
+    /// The valid TODO formats: standard, doc-tag, google, bracketed or owner-date,
+    /// overriding the config file, which defaults to standard
+    #[clap(long = "todo-format", value_delimiter = ',')]
+    pub formats: Vec<String>,
+
+    /// A chrono format that due dates of a single day can be written in, can be given
+    /// multiple times, overriding the config file, which defaults to %Y-%m-%d
+    #[clap(long = "date-format")]
+    pub date_formats: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ReassignArgs {
+    /// The current owner of the TODOs
+    #[clap(long)]
+    pub from: String,
+
+    /// The new owner of the TODOs
+    #[clap(long)]
+    pub to: String,
+
+    #[command(flatten)]
+    pub rewrite: RewriteArgs,
+}
+
+#[derive(Args, Debug)]
+#[command(group(clap::ArgGroup::new("due").required(true).args(["by", "to"])))]
+pub struct PostponeArgs {
+    /// Only postpone TODOs of this owner, can be given multiple times
+    #[clap(long)]
+    pub owner: Vec<String>,
+
+    /// Move the due dates by a number of days or weeks, like 30d or 2w
+    #[clap(long)]
+    pub by: Option<String>,
+
+    /// Set the due dates to this date (YYYY-MM-DD), also for due dates written as a sprint
+    /// or milestone
+    #[clap(long)]
+    pub to: Option<NaiveDate>,
+
+    #[command(flatten)]
+    pub rewrite: RewriteArgs,
+}
+
+/// The options shared by the subcommands that rewrite TODOs.
+#[derive(Args, Debug)]
+pub struct RewriteArgs {
+    /// The config file, searched for upward from the current directory if not given
+    #[clap(long = "config")]
+    pub config_file_path: Option<String>,
+
+    /// Only change TODOs with warnings of these types: overdue, due-soon, stale,
+    /// invalid-format, policy-violation or unknown-due-date
+    #[clap(long = "type", value_delimiter = ',')]
+    pub types: Vec<String>,
+
+    /// Only change TODOs in files under this path, relative to the config file
+    #[clap(long)]
+    pub path_prefix: Vec<String>,
+
+    /// Print the changes as a unified diff instead of writing them. Without it the files
+    /// are written right away, there is no confirmation step
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Evaluate deadlines for --type as of this date (YYYY-MM-DD) instead of the current date
+    #[clap(long)]
+    pub today: Option<NaiveDate>,
 }
 
 impl Cli {
     pub fn validate(&self) -> Result<(), String> {
-        if self.format != "table" && self.format != "json" {
-            Err("Invalid format. Supported formats are 'table' and 'json'.".to_string())
+        validate_format(&self.format, &["table", "json", "stats", "budgets"])?;
+        if !["error", "warning", "never"].contains(&self.fail_on.as_str()) {
+            return Err(
+                "Invalid fail-on. Supported values are 'error', 'warning' and 'never'.".to_string(),
+            );
+        }
+        validate_types(&self.types)?;
+        validate_display_date_format(&self.display_date_format)?;
+        self.default_due_date(self.today.unwrap_or_else(|| Local::now().date_naive()))?;
+        if !["file", "owner", "type", "none"].contains(&self.group_by.as_str()) {
+            return Err(
+                "Invalid group-by. Supported values are 'file', 'owner', 'type' and 'none'."
+                    .to_string(),
+            );
+        }
+        if !["line", "due", "owner", "type"].contains(&self.sort_by.as_str()) {
+            return Err(
+                "Invalid sort-by. Supported values are 'line', 'due', 'owner' and 'type'."
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// The lowest severity that makes the run fail, or `None` if it never fails.
+    pub fn fail_on_severity(&self) -> Option<Severity> {
+        if self.exit_zero {
+            return None;
+        }
+        match self.fail_on.as_str() {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            _ => None,
+        }
+    }
+
+    /// Resolves `--default-due` to a date, counting days from `today`.
+    pub fn default_due_date(&self, today: NaiveDate) -> Result<NaiveDate, String> {
+        let invalid = || {
+            format!(
+                "Invalid default-due '{}'. Use a date (YYYY-MM-DD) or a number of days or weeks \
+                 from today like '+90d' or '+2w'.",
+                self.default_due
+            )
+        };
+        if self.default_due.starts_with('+') {
+            let days = parse_days(&self.default_due).ok_or_else(invalid)?;
+            today.checked_add_days(Days::new(days)).ok_or_else(invalid)
         } else {
-            Ok(())
+            NaiveDate::parse_from_str(&self.default_due, "%Y-%m-%d").map_err(|_| invalid())
         }
     }
+
+    pub fn now(&self, deadlines: &Deadlines) -> DateTime<FixedOffset> {
+        deadlines.now(self.today)
+    }
+}
+
+impl DiffArgs {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_format(&self.format, &["table", "json"])?;
+        validate_display_date_format(&self.display_date_format)
+    }
+}
+
+impl PostponeArgs {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(by) = &self.by {
+            if parse_days(by).is_none() {
+                return Err(format!(
+                    "Invalid by '{}'. Use a number of days or weeks like '30d' or '2w'.",
+                    by
+                ));
+            }
+        }
+        validate_types(&self.rewrite.types)
+    }
+}
+
+impl ReassignArgs {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_types(&self.rewrite.types)
+    }
+}
+
+impl RewriteArgs {
+    pub fn now(&self, deadlines: &Deadlines) -> DateTime<FixedOffset> {
+        deadlines.now(self.today)
+    }
+}
+
+/// Parses a number of days or weeks like `30d`, `+30d` or `2w` into days.
+pub fn parse_days(value: &str) -> Option<u64> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    if let Some(days) = value.strip_suffix('d') {
+        days.parse().ok()
+    } else if let Some(weeks) = value.strip_suffix('w') {
+        weeks.parse::<u64>().ok()?.checked_mul(7)
+    } else {
+        None
+    }
+}
+
+fn validate_types(types: &[String]) -> Result<(), String> {
+    match types
+        .iter()
+        .find(|t| !WARNING_TYPES.iter().any(|(name, _)| name == t))
+    {
+        Some(unknown) => Err(format!(
+            "Invalid type '{}'. Supported types are 'overdue', 'due-soon', 'stale', \
+             'invalid-format', 'policy-violation' and 'unknown-due-date'.",
+            unknown
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a chrono format can write dates, which fails for times and unknown
+/// specifiers.
+fn validate_display_date_format(format: &str) -> Result<(), String> {
+    let mut written = String::new();
+    write!(written, "{}", NaiveDate::default().format(format)).map_err(|_| {
+        format!(
+            "Invalid display-date-format '{}'. Use a chrono format for dates like '%d.%m.%Y'.",
+            format
+        )
+    })
+}
+
+fn validate_format(format: &str, supported: &[&str]) -> Result<(), String> {
+    if supported.contains(&format) {
+        return Ok(());
+    }
+    let quoted: Vec<String> = supported.iter().map(|f| format!("'{}'", f)).collect();
+    let (last, rest) = quoted.split_last().unwrap();
+    Err(format!(
+        "Invalid format. Supported formats are {} and {}.",
+        rest.join(", "),
+        last
+    ))
 }
@@ -1,28 +1,647 @@
+use crate::business_days::BusinessDays;
+use crate::calendar::Calendar;
+use crate::cli::Cli;
+use crate::dates::Deadlines;
+use crate::languages::LanguageConfig;
+use regex::Regex;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub rules: Vec<Rule>,
     pub parameters: Parameters,
+    #[serde(default)]
+    pub policy: Policy,
+    #[serde(default)]
+    pub severity: Severities,
+    #[serde(default)]
+    pub budgets: Budgets,
+    /// The git emails and names of each owner, for `--mine`.
+    #[serde(default)]
+    pub owner_aliases: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub views: BTreeMap<String, View>,
+    pub business_days: Option<BusinessDays>,
+    /// The sprints and milestones of the `calendar` parameter.
+    #[serde(skip)]
+    pub calendar: Calendar,
+    /// When TODOs are due, from the `timezone` and `due_at` parameters.
+    #[serde(skip)]
+    pub deadlines: Deadlines,
 }
 
+/// A named filter expression, selected with `--view`.
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct View {
+    pub filter: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Rule {
     pub paths: Vec<PathBuf>,
     pub language: String,
     pub file_extensions: Option<Vec<String>>,
+    /// Files and directories to skip, relative to the config file.
+    #[serde(default)]
+    pub exclude: Vec<PathBuf>,
+    pub budget: Option<Budget>,
+    pub due_in: Option<u32>,
+    pub check_format_only: Option<bool>,
+    pub check_due_only: Option<bool>,
+    pub max_comment_length: Option<usize>,
+    pub team: Option<String>,
+    pub labels: Option<Vec<String>>,
+    pub keywords: Option<Vec<String>>,
+    pub formats: Option<Vec<TodoFormat>>,
+    pub date_formats: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Parameters {
     pub editor_url: Option<String>,
+    pub stale_after_days: Option<u32>,
+    pub implicit_due_days: Option<u32>,
+    /// A TOML or CSV file with the end dates of sprints and milestones, relative to the
+    /// config file.
+    pub calendar: Option<String>,
+    /// The IANA timezone that due dates are in, e.g. `Europe/Berlin`, instead of the
+    /// local one.
+    pub timezone: Option<String>,
+    pub due_at: Option<DueAt>,
+    pub due_in: Option<u32>,
+    pub check_format_only: Option<bool>,
+    pub check_due_only: Option<bool>,
+    pub max_comment_length: Option<usize>,
+    pub team: Option<String>,
+    pub labels: Option<Vec<String>>,
+    pub keywords: Option<Vec<String>>,
+    pub formats: Option<Vec<TodoFormat>>,
+    pub date_formats: Option<Vec<String>>,
+}
+
+/// The settings that apply to the files of a rule, resolved from the command line, the
+/// fragments of the file's directories, the rule and `[parameters]`, in that order of
+/// precedence.
+#[derive(Debug, Clone)]
+pub struct RuleSettings {
+    pub due_in: u32,
+    pub check_format_only: bool,
+    pub check_due_only: bool,
+    pub max_comment_length: usize,
+    pub team: Option<String>,
+    pub labels: Vec<String>,
+    pub keywords: Vec<String>,
+    pub formats: Vec<TodoFormat>,
+    /// The chrono formats that due dates of a single day can be written in.
+    pub date_formats: Vec<String>,
+}
+
+/// A `.todo-reminder.toml` file inside a rule path, which adjusts the settings for the files
+/// of its directory and its subdirectories. Fragments in deeper directories take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fragment {
+    /// Skips the directory and its subdirectories entirely.
+    #[serde(default)]
+    pub disable: bool,
+    /// Files and directories to skip, relative to the fragment.
+    #[serde(default)]
+    pub exclude: Vec<PathBuf>,
+    pub keywords: Option<Vec<String>>,
+    pub formats: Option<Vec<TodoFormat>>,
+    pub date_formats: Option<Vec<String>>,
+    pub due_in: Option<u32>,
+    pub check_format_only: Option<bool>,
+    pub check_due_only: Option<bool>,
+    pub max_comment_length: Option<usize>,
+    pub team: Option<String>,
+    pub labels: Option<Vec<String>>,
+}
+
+/// Limits on the number of TODOs. Exceeding any of them makes the run fail.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    pub max_todos: Option<usize>,
+    pub max_overdue: Option<usize>,
+    pub max_invalid: Option<usize>,
+}
+
+/// Budgets for directory prefixes (relative to the config file) and owners, in addition
+/// to the ones set on each rule.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budgets {
+    #[serde(default)]
+    pub directories: BTreeMap<String, Budget>,
+    #[serde(default)]
+    pub owners: BTreeMap<String, Budget>,
+}
+
+/// Additional requirements for TODOs with a valid format, reported as policy violations.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    pub max_due_horizon_days: Option<u32>,
+    pub min_description_length: Option<usize>,
+    #[serde(default)]
+    pub require_issue_reference: bool,
+    pub issue_reference_pattern: Option<String>,
+    #[serde(default)]
+    pub forbid_past_date_on_new_todos: bool,
+    #[serde(skip)]
+    pub issue_reference_regex: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The time of its due date at which a TODO becomes overdue, unless the comment gives one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DueAt {
+    #[default]
+    StartOfDay,
+    EndOfDay,
+}
+
+/// A convention for writing the due date and owner of a TODO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TodoFormat {
+    /// `TODO: 2024-01-01 @owner text`
+    Standard,
+    /// `@todo 2024-01-01 @owner text`, as in PHPDoc and JSDoc
+    DocTag,
+    /// `TODO(owner): text`, with an optional date at the start of the text
+    Google,
+    /// `TODO [2024-01-01] (owner) text`
+    Bracketed,
+    /// `FIXME(owner, 2024-01-01) text`
+    OwnerDate,
+}
+
+/// The severity of each warning type. Overdue TODOs can be given a lower severity during
+/// a grace period before they escalate to `overdue`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Severities {
+    pub invalid_format: Severity,
+    pub stale: Severity,
+    pub due_soon: Severity,
+    pub overdue: Severity,
+    pub grace_period_days: u32,
+    pub grace_period: Severity,
+    pub policy_violation: Severity,
+    pub unknown_due_date: Severity,
+}
+
+impl Default for Severities {
+    fn default() -> Self {
+        Self {
+            invalid_format: Severity::Warning,
+            stale: Severity::Warning,
+            due_soon: Severity::Warning,
+            overdue: Severity::Error,
+            grace_period_days: 0,
+            grace_period: Severity::Warning,
+            policy_violation: Severity::Warning,
+            unknown_due_date: Severity::Warning,
+        }
+    }
 }
 
+const DEFAULT_ISSUE_REFERENCE_PATTERN: &str = r"#\d+|\b[A-Z][A-Z0-9]+-\d+\b|https?://\S+";
+
+pub const DEFAULT_KEYWORDS: [&str; 2] = ["TODO", "FIXME"];
+pub const DEFAULT_FORMATS: [TodoFormat; 1] = [TodoFormat::Standard];
+pub const DEFAULT_DATE_FORMATS: [&str; 1] = ["%Y-%m-%d"];
+
+/// The name of the config file created by `init`.
+pub const DEFAULT_CONFIG_FILE_NAME: &str = "todo-reminder.toml";
+/// Dedicated config files, searched for in the current directory and its parents.
+const CONFIG_FILE_NAMES: [&str; 2] = [".todo-reminder.toml", DEFAULT_CONFIG_FILE_NAME];
+/// Fragments share their name with dedicated config files, but have no rules.
+pub const FRAGMENT_FILE_NAME: &str = ".todo-reminder.toml";
+/// Files of other tools that can embed the config in one of the given tables.
+const EMBEDDING_FILE_NAMES: [&str; 2] = ["pyproject.toml", "Cargo.toml"];
+
 impl Config {
-    pub fn from_file(path: &str) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+    /// Returns the given config file, or searches for one upward from the current directory.
+    /// In each directory, dedicated config files take precedence over embedded sections.
+    pub fn locate(path: Option<&str>) -> anyhow::Result<PathBuf> {
+        if let Some(path) = path {
+            return Ok(PathBuf::from(path));
+        }
+
+        let current_dir = std::env::current_dir()?;
+        for dir in current_dir.ancestors() {
+            for name in CONFIG_FILE_NAMES {
+                let path = dir.join(name);
+                if path.is_file() && !is_fragment(&path) {
+                    return Ok(path);
+                }
+            }
+            for name in EMBEDDING_FILE_NAMES {
+                let path = dir.join(name);
+                // Files that can't be parsed are not ours to complain about
+                if path.is_file() && matches!(find_embedded_section(&path), Ok(Some(_))) {
+                    return Ok(path);
+                }
+            }
+        }
+
+        anyhow::bail!(
+            "No config file found in {} or its parent directories. Create a {} file, or a \
+             [tool.todo-reminder] section in pyproject.toml or a \
+             [package.metadata.todo-reminder] section in Cargo.toml.",
+            current_dir.display(),
+            DEFAULT_CONFIG_FILE_NAME
+        )
+    }
+
+    /// Loads the config file along with the files it extends and includes, and expands the
+    /// environment variables it references.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let table = load_table(path, &mut Vec::new())?;
+        let mut config: Config = toml::Value::Table(table).try_into()?;
+        config.interpolate_env()?;
+        config.policy.compile()?;
+        if let Some(calendar) = &config.parameters.calendar {
+            config.calendar = Calendar::from_file(&Self::base_dir(path)?, calendar)?;
+        }
+        if let Some(business_days) = &mut config.business_days {
+            business_days.load(&Self::base_dir(path)?)?;
+        }
+        config.deadlines = Deadlines::new(
+            config.parameters.timezone.as_deref(),
+            config.parameters.due_at.unwrap_or_default(),
+        )?;
         Ok(config)
     }
+
+    pub fn has_budgets(&self) -> bool {
+        self.rules.iter().any(|rule| rule.budget.is_some())
+            || !self.budgets.directories.is_empty()
+            || !self.budgets.owners.is_empty()
+    }
+
+    /// Resolves git emails and names to owners. `[owner_aliases]` maps owners to their
+    /// emails and names; without a match, the local part of the email and the name are used.
+    pub fn owners_of(&self, identities: &[String]) -> Vec<String> {
+        let aliased: Vec<String> = self
+            .owner_aliases
+            .iter()
+            .filter(|(_, aliases)| {
+                aliases.iter().any(|alias| {
+                    identities
+                        .iter()
+                        .any(|identity| identity.eq_ignore_ascii_case(alias))
+                })
+            })
+            .map(|(owner, _)| owner.clone())
+            .collect();
+        if !aliased.is_empty() {
+            return aliased;
+        }
+
+        identities
+            .iter()
+            .map(|identity| match identity.split_once('@') {
+                Some((local_part, _)) => local_part.to_string(),
+                None => identity.clone(),
+            })
+            .collect()
+    }
+
+    /// Whether any feature needs to look up when TODOs were introduced with `git blame`.
+    pub fn needs_git_history(&self) -> bool {
+        self.parameters.stale_after_days.is_some()
+            || self.parameters.implicit_due_days.is_some()
+            || self.policy.forbid_past_date_on_new_todos
+    }
+
+    fn interpolate_env(&mut self) -> anyhow::Result<()> {
+        if let Some(editor_url) = &self.parameters.editor_url {
+            self.parameters.editor_url = Some(interpolate_env(editor_url)?);
+        }
+        if let Some(calendar) = &self.parameters.calendar {
+            self.parameters.calendar = Some(interpolate_env(calendar)?);
+        }
+        for rule in &mut self.rules {
+            for path in rule.paths.iter_mut().chain(&mut rule.exclude) {
+                *path = PathBuf::from(interpolate_env(&path.to_string_lossy())?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the absolute directory of the config file, which rule paths are relative to.
+    pub fn base_dir(path: &Path) -> anyhow::Result<PathBuf> {
+        let config_file_dir = path.parent().unwrap();
+        if config_file_dir.is_absolute() {
+            Ok(config_file_dir.to_path_buf())
+        } else {
+            Ok(std::env::current_dir()?.join(config_file_dir))
+        }
+    }
+}
+
+/// Reads a config file as a table, resolving `extends` and `include` relative to the file.
+/// The extended file comes first, then the included files in order, then the file itself.
+/// `chain` holds the files being loaded, to detect cycles.
+fn load_table(path: &Path, chain: &mut Vec<PathBuf>) -> anyhow::Result<toml::Table> {
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+    if chain.contains(&canonical_path) {
+        anyhow::bail!("Config file {} extends or includes itself", path.display());
+    }
+    chain.push(canonical_path);
+
+    let mut table = read_table(path)?;
+    let dir = path.parent().unwrap();
+    let mut merged = toml::Table::new();
+
+    if let Some(extends) = table.remove("extends") {
+        let extends: String = extends
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("extends must be a path in {}", path.display()))?;
+        merged = load_table(&dir.join(interpolate_env(&extends)?), chain)?;
+    }
+    if let Some(include) = table.remove("include") {
+        let include: Vec<String> = include.try_into().map_err(|_| {
+            anyhow::anyhow!("include must be a list of paths in {}", path.display())
+        })?;
+        for include_path in include {
+            let included = load_table(&dir.join(interpolate_env(&include_path)?), chain)?;
+            merge_tables(&mut merged, included);
+        }
+    }
+    merge_tables(&mut merged, table);
+
+    chain.pop();
+    Ok(merged)
+}
+
+fn read_table(path: &Path) -> anyhow::Result<toml::Table> {
+    match embedded_sections(path) {
+        Some(sections) => match find_embedded_section(path)? {
+            Some(toml::Value::Table(table)) => Ok(table),
+            _ => {
+                let sections: Vec<String> = sections
+                    .iter()
+                    .map(|section| format!("[{}]", section.join(".")))
+                    .collect();
+                anyhow::bail!("No {} section in {}", sections.join(" or "), path.display())
+            }
+        },
+        None => Ok(toml::from_str(&std::fs::read_to_string(path)?)?),
+    }
+}
+
+/// Merges `overlay` into `base`. Rules are appended, tables are merged key by key and any
+/// other value is replaced.
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Array(base_rules)), toml::Value::Array(rules)) if key == "rules" => {
+                base_rules.extend(rules);
+            }
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                merge_tables(base_table, table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Replaces `${NAME}` with the value of the environment variable `NAME`.
+pub fn interpolate_env(value: &str) -> anyhow::Result<String> {
+    let regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap();
+    let mut result = String::new();
+    let mut last_end = 0;
+
+    for captures in regex.captures_iter(value) {
+        let matched = captures.get(0).unwrap();
+        let name = &captures[1];
+        let env_value = std::env::var(name).map_err(|_| {
+            anyhow::anyhow!(
+                "Environment variable {} used in the config is not set",
+                name
+            )
+        })?;
+        result.push_str(&value[last_end..matched.start()]);
+        result.push_str(&env_value);
+        last_end = matched.end();
+    }
+    result.push_str(&value[last_end..]);
+
+    Ok(result)
+}
+
+/// Whether the file is a fragment for a subdirectory rather than a complete config.
+fn is_fragment(path: &Path) -> bool {
+    match read_table(path) {
+        Ok(table) => ["rules", "extends", "include"]
+            .iter()
+            .all(|key| !table.contains_key(*key)),
+        // Let loading the file report the error
+        Err(_) => false,
+    }
+}
+
+/// Returns the tables that can hold the config if the file belongs to another tool.
+pub fn embedded_sections(path: &Path) -> Option<&'static [&'static [&'static str]]> {
+    match path.file_name()?.to_str()? {
+        "pyproject.toml" => Some(&[&["tool", "todo-reminder"]]),
+        "Cargo.toml" => Some(&[
+            &["package", "metadata", "todo-reminder"],
+            &["workspace", "metadata", "todo-reminder"],
+        ]),
+        _ => None,
+    }
+}
+
+fn find_embedded_section(path: &Path) -> anyhow::Result<Option<toml::Value>> {
+    let sections = match embedded_sections(path) {
+        Some(sections) => sections,
+        None => return Ok(None),
+    };
+    let content = std::fs::read_to_string(path)?;
+    let document: toml::Value = toml::from_str(&content)?;
+
+    Ok(sections.iter().find_map(|section| {
+        section
+            .iter()
+            .try_fold(&document, |value, key| value.get(key))
+            .cloned()
+    }))
+}
+
+impl Rule {
+    pub fn file_extensions(&self, language_config: &LanguageConfig) -> Vec<String> {
+        self.file_extensions.clone().unwrap_or_else(|| {
+            language_config
+                .file_extensions
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        })
+    }
+}
+
+impl Policy {
+    fn compile(&mut self) -> anyhow::Result<()> {
+        if self.require_issue_reference {
+            let pattern = self
+                .issue_reference_pattern
+                .as_deref()
+                .unwrap_or(DEFAULT_ISSUE_REFERENCE_PATTERN);
+            self.issue_reference_regex = Some(Regex::new(pattern)?);
+        }
+        Ok(())
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl TodoFormat {
+    pub const ALL: [TodoFormat; 5] = [
+        TodoFormat::Standard,
+        TodoFormat::DocTag,
+        TodoFormat::Google,
+        TodoFormat::Bracketed,
+        TodoFormat::OwnerDate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoFormat::Standard => "standard",
+            TodoFormat::DocTag => "doc-tag",
+            TodoFormat::Google => "google",
+            TodoFormat::Bracketed => "bracketed",
+            TodoFormat::OwnerDate => "owner-date",
+        }
+    }
+}
+
+impl RuleSettings {
+    /// Resolves the settings of a rule, without the command-line overrides.
+    pub fn resolve(parameters: &Parameters, rule: &Rule) -> Self {
+        Self {
+            due_in: rule.due_in.or(parameters.due_in).unwrap_or(0),
+            check_format_only: rule
+                .check_format_only
+                .or(parameters.check_format_only)
+                .unwrap_or(false),
+            check_due_only: rule
+                .check_due_only
+                .or(parameters.check_due_only)
+                .unwrap_or(false),
+            max_comment_length: rule
+                .max_comment_length
+                .or(parameters.max_comment_length)
+                .unwrap_or(100),
+            team: rule.team.clone().or_else(|| parameters.team.clone()),
+            labels: rule
+                .labels
+                .clone()
+                .or_else(|| parameters.labels.clone())
+                .unwrap_or_default(),
+            keywords: rule
+                .keywords
+                .clone()
+                .or_else(|| parameters.keywords.clone())
+                .unwrap_or_else(|| DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect()),
+            formats: rule
+                .formats
+                .clone()
+                .or_else(|| parameters.formats.clone())
+                .unwrap_or_else(|| DEFAULT_FORMATS.to_vec()),
+            date_formats: rule
+                .date_formats
+                .clone()
+                .or_else(|| parameters.date_formats.clone())
+                .unwrap_or_else(|| DEFAULT_DATE_FORMATS.iter().map(|f| f.to_string()).collect()),
+        }
+    }
+
+    pub fn apply_fragment(&mut self, fragment: &Fragment) {
+        // Like on the command line, setting either check mode replaces both of them
+        if fragment.check_format_only.is_some() || fragment.check_due_only.is_some() {
+            self.check_format_only = fragment.check_format_only.unwrap_or(false);
+            self.check_due_only = fragment.check_due_only.unwrap_or(false);
+        }
+        if let Some(due_in) = fragment.due_in {
+            self.due_in = due_in;
+        }
+        if let Some(max_comment_length) = fragment.max_comment_length {
+            self.max_comment_length = max_comment_length;
+        }
+        if let Some(team) = &fragment.team {
+            self.team = Some(team.clone());
+        }
+        if let Some(labels) = &fragment.labels {
+            self.labels = labels.clone();
+        }
+        if let Some(keywords) = &fragment.keywords {
+            self.keywords = keywords.clone();
+        }
+        if let Some(formats) = &fragment.formats {
+            self.formats = formats.clone();
+        }
+        if let Some(date_formats) = &fragment.date_formats {
+            self.date_formats = date_formats.clone();
+        }
+    }
+
+    pub fn with_cli(mut self, cli: &Cli) -> Self {
+        // The check modes conflict with each other, so either flag on the command line
+        // overrides both of them
+        if cli.check_format_only || cli.check_due_only {
+            self.check_format_only = cli.check_format_only;
+            self.check_due_only = cli.check_due_only;
+        }
+        if let Some(due_in) = cli.due_in {
+            self.due_in = due_in;
+        }
+        if let Some(max_comment_length) = cli.max_comment_length {
+            self.max_comment_length = max_comment_length;
+        }
+        self
+    }
+}
+
+impl Fragment {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?, path)
+    }
+
+    /// Parses the content of the fragment at `path`, which may come from a git revision.
+    pub fn parse(content: &str, path: &Path) -> anyhow::Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| anyhow::anyhow!("Invalid config fragment {}: {}", path.display(), e))
+    }
 }
@@ -0,0 +1,344 @@
+use crate::config::DueAt;
+use anyhow::Result;
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeDelta, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// The due dates that are accepted besides days: ISO weeks like `2025-W14`, months like
+/// `2025-03`, quarters like `2025-Q2` and years like `2025`.
+const PARTIAL_DATE_PATTERN: &str = r"\d{4}(?:-\d{2}|-[Qq][1-4]|-[Ww]\d{2})?";
+/// The time of day that can follow a day, e.g. `T17:00` in `2024-05-01T17:00`.
+const TIME_OF_DAY_PATTERN: &str = r"T\d{2}:\d{2}";
+static TIME_OF_DAY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("{}$", TIME_OF_DAY_PATTERN)).unwrap());
+
+/// How precisely a due date was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DatePrecision {
+    #[default]
+    Day,
+    /// A day with a time of day.
+    Time,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl DatePrecision {
+    /// Writes a date with this precision, e.g. `2025-Q2` for any day of the second quarter.
+    /// Days are written in `day_format`.
+    pub fn format(self, due: NaiveDateTime, day_format: &str) -> String {
+        let date = due.date();
+        match self {
+            DatePrecision::Day => date.format(day_format).to_string(),
+            DatePrecision::Time => format!("{}T{}", date.format(day_format), due.format("%H:%M")),
+            DatePrecision::Week => date.format("%G-W%V").to_string(),
+            DatePrecision::Month => date.format("%Y-%m").to_string(),
+            DatePrecision::Quarter => format!("{}-Q{}", date.year(), date.month0() / 3 + 1),
+            DatePrecision::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+/// Why a due date that has the shape of a date can't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateError {
+    /// The date doesn't exist, like `2024-02-30`.
+    Invalid,
+    /// The date reads as different days in different formats, like `05/06/2024` with both
+    /// `%m/%d/%Y` and `%d/%m/%Y`.
+    Ambiguous(Vec<NaiveDate>),
+}
+
+impl DateError {
+    pub fn describe(&self, written: &str) -> String {
+        match self {
+            DateError::Invalid => format!("{} is not a valid date", written),
+            DateError::Ambiguous(dates) => format!(
+                "{} is ambiguous, it could be {}",
+                written,
+                dates
+                    .iter()
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        }
+    }
+}
+
+/// The chrono formats that due dates of a single day can be written in, optionally followed
+/// by a time of day. Weeks, months, quarters and years are always written as in ISO 8601.
+#[derive(Debug, Clone)]
+pub struct DateFormats {
+    formats: Vec<String>,
+    pattern: String,
+    day_pattern: String,
+    /// Matches a due date at the start of a text, followed by a space or punctuation.
+    leading_date: Regex,
+}
+
+impl DateFormats {
+    pub fn new(formats: &[String]) -> Result<Self> {
+        if formats.is_empty() {
+            anyhow::bail!("At least one date format is required");
+        }
+        let patterns = formats
+            .iter()
+            .map(|format| {
+                date_format_pattern(format)
+                    .map_err(|e| anyhow::anyhow!("Invalid date format \"{}\": {}", format, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let day_pattern = format!("(?:{})(?:{})?", patterns.join("|"), TIME_OF_DAY_PATTERN);
+        let pattern = format!("(?:{}|{})", day_pattern, PARTIAL_DATE_PATTERN);
+
+        Ok(DateFormats {
+            formats: formats.to_vec(),
+            leading_date: Regex::new(&format!(r"(?i)^({})(?:\s|$|[,)\]])", pattern))?,
+            pattern,
+            day_pattern,
+        })
+    }
+
+    pub fn formats(&self) -> &[String] {
+        &self.formats
+    }
+
+    /// A regex for the due dates in any of the formats, without groups.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// A regex for the due dates of a single day, optionally with a time of day, without
+    /// the weeks, months, quarters and years of `pattern`.
+    pub fn day_pattern(&self) -> &str {
+        &self.day_pattern
+    }
+
+    /// The due date at the start of a text, if it has the shape of one.
+    pub fn find<'a>(&self, text: &'a str) -> Option<&'a str> {
+        Some(self.leading_date.captures(text)?.get(1)?.as_str())
+    }
+
+    /// Parses a due date, resolving weeks, months, quarters and years to their last day.
+    /// Only days can have a time of day, the others are at midnight.
+    pub fn parse(&self, text: &str) -> Result<(NaiveDateTime, DatePrecision), DateError> {
+        let (text, time_of_day) = split_time_of_day(text);
+        if let Some(time_of_day) = time_of_day {
+            let time =
+                NaiveTime::parse_from_str(time_of_day, "%H:%M").map_err(|_| DateError::Invalid)?;
+            return match self.parse(text)? {
+                (due, DatePrecision::Day) => Ok((due.date().and_time(time), DatePrecision::Time)),
+                _ => Err(DateError::Invalid),
+            };
+        }
+
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        for format in &self.formats {
+            if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+                if !dates.contains(&date) {
+                    dates.push(date);
+                }
+            }
+        }
+        match dates.len() {
+            0 => parse_partial_date(text)
+                .map(|(date, precision)| (date.into(), precision))
+                .ok_or(DateError::Invalid),
+            1 => Ok((dates[0].into(), DatePrecision::Day)),
+            _ => Err(DateError::Ambiguous(dates)),
+        }
+    }
+
+    /// Writes a day in the format of the date `like` if it has one, or else in the first
+    /// format that can only be read as that day, falling back to `%Y-%m-%d`. The time of
+    /// day of `like` isn't written.
+    pub fn write(&self, date: NaiveDate, like: Option<&str>) -> String {
+        let like_format = like.and_then(|like| {
+            let (like, _) = split_time_of_day(like);
+            self.formats
+                .iter()
+                .find(|format| NaiveDate::parse_from_str(like, format).is_ok())
+        });
+        like_format
+            .into_iter()
+            .chain(&self.formats)
+            .map(|format| date.format(format).to_string())
+            .find(|written| self.parse(written) == Ok((date.into(), DatePrecision::Day)))
+            .unwrap_or_else(|| date.format("%Y-%m-%d").to_string())
+    }
+}
+
+/// Splits the time of day off a due date like `2024-05-01T17:00`.
+pub fn split_time_of_day(text: &str) -> (&str, Option<&str>) {
+    match TIME_OF_DAY.find(text) {
+        Some(time_of_day) => (
+            &text[..time_of_day.start()],
+            Some(&time_of_day.as_str()[1..]),
+        ),
+        None => (text, None),
+    }
+}
+
+/// Translates a chrono format for a day into a regex, e.g. `\d{2}\.\d{2}\.\d{4}` for
+/// `%d.%m.%Y`, or says why the format can't be used. Only the specifiers for years, months
+/// and days are supported.
+pub fn date_format_pattern(format: &str) -> Result<String, String> {
+    let mut pattern = String::new();
+    let (mut has_year, mut has_month, mut has_day) = (false, false, false);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+            continue;
+        }
+        let mut specifier = chars.next().map(String::from).unwrap_or_default();
+        if specifier == "-" {
+            specifier.extend(chars.next());
+        }
+        pattern.push_str(match specifier.as_str() {
+            "Y" => {
+                has_year = true;
+                r"\d{4}"
+            }
+            "y" => {
+                has_year = true;
+                r"\d{2}"
+            }
+            "m" | "d" => {
+                has_month |= specifier == "m";
+                has_day |= specifier == "d";
+                r"\d{2}"
+            }
+            "-m" | "-d" => {
+                has_month |= specifier == "-m";
+                has_day |= specifier == "-d";
+                r"\d{1,2}"
+            }
+            "b" => {
+                has_month = true;
+                r"[A-Za-z]{3}"
+            }
+            "B" => {
+                has_month = true;
+                r"[A-Za-z]+"
+            }
+            "%" => "%",
+            _ => {
+                return Err(format!(
+                    "%{} isn't supported, use %Y, %y, %m, %-m, %b, %B, %d or %-d",
+                    specifier
+                ))
+            }
+        });
+    }
+    if !(has_year && has_month && has_day) {
+        return Err("it must have a year, a month and a day".to_string());
+    }
+    Ok(pattern)
+}
+
+/// Parses a week, month, quarter or year of `PARTIAL_DATE_PATTERN`, resolving it to its
+/// last day.
+pub fn parse_partial_date(text: &str) -> Option<(NaiveDate, DatePrecision)> {
+    let (year, rest) = match text.split_once('-') {
+        Some((year, rest)) => (year.parse::<i32>().ok()?, Some(rest)),
+        None => (text.parse::<i32>().ok()?, None),
+    };
+    let Some(rest) = rest else {
+        return Some((NaiveDate::from_ymd_opt(year, 12, 31)?, DatePrecision::Year));
+    };
+
+    if let Some(quarter) = rest.strip_prefix(['Q', 'q']) {
+        let quarter: u32 = quarter.parse().ok().filter(|q| (1..=4).contains(q))?;
+        return Some((
+            last_day_of_month(year, quarter * 3)?,
+            DatePrecision::Quarter,
+        ));
+    }
+    if let Some(week) = rest.strip_prefix(['W', 'w']) {
+        let date = NaiveDate::from_isoywd_opt(year, week.parse().ok()?, Weekday::Sun)?;
+        return Some((date, DatePrecision::Week));
+    }
+    Some((
+        last_day_of_month(year, rest.parse().ok()?)?,
+        DatePrecision::Month,
+    ))
+}
+
+/// When TODOs are due: the timezone their due dates are in, the local one by default, and
+/// the time of the due date they become overdue at.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deadlines {
+    timezone: Option<Tz>,
+    due_at: DueAt,
+}
+
+impl Deadlines {
+    pub fn new(timezone: Option<&str>, due_at: DueAt) -> Result<Self> {
+        let timezone = timezone
+            .map(|timezone| {
+                timezone.parse::<Tz>().map_err(|_| {
+                    anyhow::anyhow!(
+                        "Unknown timezone \"{}\", use an IANA name like \"Europe/Berlin\"",
+                        timezone
+                    )
+                })
+            })
+            .transpose()?;
+        Ok(Deadlines { timezone, due_at })
+    }
+
+    /// The moment a TODO becomes overdue. Due dates with a time of day are due at that
+    /// time, the others at the start or the end of their day.
+    pub fn due(
+        &self,
+        due: NaiveDateTime,
+        precision: DatePrecision,
+    ) -> Option<DateTime<FixedOffset>> {
+        let due = match (precision, self.due_at) {
+            (DatePrecision::Time, _) => due,
+            (_, DueAt::StartOfDay) => due.date().and_time(NaiveTime::MIN),
+            (_, DueAt::EndOfDay) => due.date().and_hms_nano_opt(23, 59, 59, 999_999_999)?,
+        };
+        match self.timezone {
+            Some(timezone) => resolve_local(&timezone, due),
+            None => resolve_local(&Local, due),
+        }
+    }
+
+    /// The start of `today`, or the current time if no date was given.
+    pub fn now(&self, today: Option<NaiveDate>) -> DateTime<FixedOffset> {
+        let now = Utc::now();
+        today
+            .and_then(|today| self.due(today.into(), DatePrecision::Time))
+            .unwrap_or_else(|| match self.timezone {
+                Some(timezone) => now.with_timezone(&timezone).fixed_offset(),
+                None => now.with_timezone(&Local).fixed_offset(),
+            })
+    }
+}
+
+/// The moment of a local time. Times skipped by a daylight saving change, like 02:30 on the
+/// day clocks go from 02:00 to 03:00, are moved forward an hour at a time until they exist,
+/// so that the TODO is still due.
+fn resolve_local<T: TimeZone>(timezone: &T, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+    // Some zones skipped a whole day when they moved across the date line
+    (0..=24)
+        .filter_map(|hours| local.checked_add_signed(TimeDelta::hours(hours)))
+        .find_map(|local| timezone.from_local_datetime(&local).earliest())
+        .map(|due| due.fixed_offset())
+}
+
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    first
+        .checked_add_months(Months::new(1))?
+        .checked_sub_days(Days::new(1))
+}
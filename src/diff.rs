@@ -0,0 +1,236 @@
+use crate::cli::DiffArgs;
+use crate::config::Config;
+use crate::dates::DatePrecision;
+use crate::git;
+use crate::languages::get_language_configs;
+use crate::output::{print_diff_json, print_diff_table};
+use crate::todo_analyzer::{TodoAnalyzer, TodoItem};
+use crate::walk::{walk_rules, Tree};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoChangeKind {
+    Added,
+    Resolved,
+    Modified,
+}
+
+#[derive(Debug)]
+pub struct TodoChange {
+    pub file: String,
+    pub kind: TodoChangeKind,
+    pub old: Option<TodoItem>,
+    pub new: Option<TodoItem>,
+}
+
+impl TodoChange {
+    /// The most recent version of the TODO, i.e. the removed one for resolved TODOs.
+    pub fn todo(&self) -> &TodoItem {
+        self.new.as_ref().or(self.old.as_ref()).unwrap()
+    }
+
+    pub fn is_postponed(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => match (old.due_date, new.due_date) {
+                (Some(old_due), Some(new_due)) => new_due > old_due,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn is_reassigned(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => old.owner != new.owner,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TodoDiff {
+    pub changes: Vec<TodoChange>,
+}
+
+impl TodoDiff {
+    pub fn count(&self, kind: TodoChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    pub fn postponed(&self) -> usize {
+        self.changes.iter().filter(|c| c.is_postponed()).count()
+    }
+
+    pub fn reassigned(&self) -> usize {
+        self.changes.iter().filter(|c| c.is_reassigned()).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} TODOs resolved, {} added, {} modified ({} postponed, {} reassigned)",
+            self.count(TodoChangeKind::Resolved),
+            self.count(TodoChangeKind::Added),
+            self.count(TodoChangeKind::Modified),
+            self.postponed(),
+            self.reassigned(),
+        )
+    }
+}
+
+pub fn run(args: &DiffArgs) -> Result<()> {
+    let config_file_path = Config::locate(args.config_file_path.as_deref())?;
+    let config = Config::from_file(&config_file_path)?;
+    let repo_root = git::repo_root(&Config::base_dir(&config_file_path)?)?;
+
+    let language_configs = get_language_configs();
+    let mut analyzer = TodoAnalyzer::new(&language_configs)?;
+    analyzer.set_deadlines(config.deadlines);
+
+    let old_todos = collect_todos(
+        &mut analyzer,
+        &config,
+        &config_file_path,
+        &repo_root,
+        &args.from,
+    )?;
+    let new_todos = collect_todos(
+        &mut analyzer,
+        &config,
+        &config_file_path,
+        &repo_root,
+        &args.to,
+    )?;
+
+    let diff = diff_todos(old_todos, new_todos);
+
+    match args.format.as_str() {
+        "json" => print_diff_json(&diff, &args.display_date_format),
+        _ => print_diff_table(&diff, args, config.parameters.editor_url.as_deref()),
+    }
+
+    Ok(())
+}
+
+/// Extracts the TODOs of every file matched by the config rules and fragments at the given
+/// revision, keyed by the file path relative to the config file. A file matched by several
+/// rules is analyzed with the one `walk_rules` picks for it, like `check` does.
+fn collect_todos(
+    analyzer: &mut TodoAnalyzer,
+    config: &Config,
+    config_file_path: &Path,
+    repo_root: &Path,
+    rev: &str,
+) -> Result<BTreeMap<String, Vec<TodoItem>>> {
+    let tree = Tree::revision(repo_root, rev)?;
+    let mut todos_by_file: BTreeMap<String, Vec<TodoItem>> = BTreeMap::new();
+
+    walk_rules(
+        config,
+        config_file_path,
+        &tree,
+        analyzer,
+        |analyzer, file| {
+            match analyzer.analyze_source(&file.content, &file.rule.language) {
+                Ok(mut todos) => {
+                    // Sprints and milestones are compared by the end dates of the calendar
+                    for todo in &mut todos {
+                        if let Some(due_name) = &todo.due_name {
+                            todo.due_date =
+                                config.calendar.resolve(due_name).and_then(|end_date| {
+                                    config.deadlines.due(end_date.into(), DatePrecision::Day)
+                                });
+                        }
+                    }
+                    todos_by_file.insert(file.relative_path, todos);
+                }
+                Err(e) => {
+                    eprintln!("Error analyzing file {}:{}: {}", rev, file.relative_path, e);
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    Ok(todos_by_file)
+}
+
+/// Pairs up the TODOs of both revisions. Identical comments are matched regardless of
+/// their line numbers so that moved code doesn't show up as a change, then the remaining
+/// ones are matched on their description to detect changed owners and due dates.
+fn diff_todos(
+    mut old_todos: BTreeMap<String, Vec<TodoItem>>,
+    mut new_todos: BTreeMap<String, Vec<TodoItem>>,
+) -> TodoDiff {
+    let mut files: Vec<String> = old_todos.keys().chain(new_todos.keys()).cloned().collect();
+    files.sort();
+    files.dedup();
+
+    let mut diff = TodoDiff::default();
+
+    for file in files {
+        let old = old_todos.remove(&file).unwrap_or_default();
+        let mut new: Vec<Option<TodoItem>> = new_todos
+            .remove(&file)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Some)
+            .collect();
+
+        let mut unmatched = Vec::new();
+        for old_todo in old {
+            let position = new
+                .iter()
+                .position(|n| n.as_ref().is_some_and(|n| n.text == old_todo.text));
+            match position {
+                Some(position) => new[position] = None,
+                None => unmatched.push(old_todo),
+            }
+        }
+
+        let mut file_changes = Vec::new();
+        for old_todo in unmatched {
+            let position = new.iter().position(|n| {
+                n.as_ref().is_some_and(|n| {
+                    !n.description.is_empty() && n.description == old_todo.description
+                })
+            });
+            match position {
+                Some(position) => {
+                    let new_todo = new[position].take().unwrap();
+                    if new_todo.owner == old_todo.owner
+                        && new_todo.due_date == old_todo.due_date
+                        && new_todo.due_name == old_todo.due_name
+                    {
+                        continue;
+                    }
+                    file_changes.push(TodoChange {
+                        file: file.clone(),
+                        kind: TodoChangeKind::Modified,
+                        old: Some(old_todo),
+                        new: Some(new_todo),
+                    });
+                }
+                None => file_changes.push(TodoChange {
+                    file: file.clone(),
+                    kind: TodoChangeKind::Resolved,
+                    old: Some(old_todo),
+                    new: None,
+                }),
+            }
+        }
+
+        file_changes.extend(new.into_iter().flatten().map(|new_todo| TodoChange {
+            file: file.clone(),
+            kind: TodoChangeKind::Added,
+            old: None,
+            new: Some(new_todo),
+        }));
+
+        file_changes.sort_by_key(|c| c.todo().line_number);
+        diff.changes.extend(file_changes);
+    }
+
+    diff
+}
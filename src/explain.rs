@@ -0,0 +1,123 @@
+use crate::cli::ExplainArgs;
+use crate::config::{Config, TodoFormat, DEFAULT_DATE_FORMATS, DEFAULT_FORMATS, DEFAULT_KEYWORDS};
+use crate::hint::LooseTodo;
+use crate::languages::get_language_configs;
+use crate::todo_analyzer::TodoAnalyzer;
+use crate::validate::{did_you_mean, suggest};
+use anyhow::anyhow;
+
+/// Prints how a single comment is read with the keywords, formats and date formats of the
+/// config file's `[parameters]`, unless the options override them. Returns whether it is a
+/// TODO with an invalid format.
+pub fn run(args: &ExplainArgs) -> anyhow::Result<bool> {
+    // Without --config, the comment is read with the defaults if no config file is found
+    let config = match Config::locate(args.config_file_path.as_deref()) {
+        Ok(config_file_path) => Some(Config::from_file(&config_file_path).map_err(|e| {
+            anyhow!(
+                "{}\nRun `todo-reminder validate-config` to check the config file",
+                e
+            )
+        })?),
+        Err(_) if args.config_file_path.is_none() => None,
+        Err(e) => return Err(e),
+    };
+    let parameters = config.as_ref().map(|config| &config.parameters);
+
+    let keywords: Vec<String> = if !args.keywords.is_empty() {
+        args.keywords.clone()
+    } else if let Some(keywords) = parameters.and_then(|p| p.keywords.clone()) {
+        keywords
+    } else {
+        DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect()
+    };
+    let mut formats = Vec::new();
+    for name in &args.formats {
+        let format = TodoFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str() == name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown TODO format \"{}\"{}",
+                    name,
+                    did_you_mean(suggest(name, TodoFormat::ALL.map(|format| format.as_str())))
+                )
+            })?;
+        formats.push(format);
+    }
+    if formats.is_empty() {
+        formats = parameters
+            .and_then(|p| p.formats.clone())
+            .unwrap_or_else(|| DEFAULT_FORMATS.to_vec());
+    }
+    let date_formats: Vec<String> = if !args.date_formats.is_empty() {
+        args.date_formats.clone()
+    } else if let Some(date_formats) = parameters.and_then(|p| p.date_formats.clone()) {
+        date_formats
+    } else {
+        DEFAULT_DATE_FORMATS.iter().map(|f| f.to_string()).collect()
+    };
+
+    let language_configs = get_language_configs();
+    let mut analyzer = TodoAnalyzer::new(&language_configs)?;
+    analyzer.set_keywords(&keywords)?;
+    analyzer.set_formats(&formats)?;
+    analyzer.set_date_formats(&date_formats)?;
+
+    let comment = args.comment.as_str();
+    if let Some(format_match) = analyzer.match_format(comment) {
+        println!("Format:      valid ({})", format_match.format.as_str());
+        println!("Keyword:     {}", &comment[format_match.keyword]);
+        println!(
+            "Due date:    {}",
+            format_match.due_date.map_or("-".to_string(), |range| {
+                let due_date = &comment[range];
+                match analyzer.date_formats().parse(due_date) {
+                    Ok(_) => due_date.to_string(),
+                    // Only the names of sprints and milestones match without being dates
+                    Err(_) => {
+                        let calendar = config.as_ref().map(|config| &config.calendar);
+                        match calendar.and_then(|calendar| calendar.resolve(due_date)) {
+                            Some(end_date) => format!("{} (ends {})", due_date, end_date),
+                            None => format!("{} (resolved with the calendar)", due_date),
+                        }
+                    }
+                }
+            })
+        );
+        println!("Owner:       @{}", &comment[format_match.owner]);
+        println!("Description: {}", comment[format_match.description].trim());
+        return Ok(false);
+    }
+
+    if analyzer.find_keyword(comment).is_none() {
+        println!("Not a TODO: none of {} found", keywords.join(", "));
+        return Ok(false);
+    }
+    println!("Format:      invalid");
+    let loose = match LooseTodo::parse(&analyzer, comment) {
+        Ok(loose) => loose,
+        Err(reason) => {
+            println!("Problems:");
+            println!("  - {}", reason);
+            return Ok(true);
+        }
+    };
+    println!("Keyword:     {}", loose.keyword);
+    println!("Due date:    {}", loose.due_date.as_deref().unwrap_or("-"));
+    println!(
+        "Owner:       {}",
+        loose
+            .owner
+            .as_ref()
+            .map_or("-".to_string(), |owner| format!("@{}", owner))
+    );
+    println!("Description: {}", loose.text);
+    if !loose.problems.is_empty() {
+        println!("Problems:");
+        for problem in &loose.problems {
+            println!("  - {}", problem);
+        }
+    }
+    println!("Did you mean: {}", loose.suggestion());
+    Ok(true)
+}
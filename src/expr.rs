@@ -0,0 +1,393 @@
+use crate::validate::{did_you_mean, suggest};
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+
+/// A value of a field or literal in a filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// `null`, `false`, `0`, empty strings and empty lists are false.
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Int(n) => *n != 0,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+}
+
+impl From<Option<String>> for Value {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(Value::Null, Value::Str)
+    }
+}
+
+impl From<Option<i64>> for Value {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(Value::Null, Value::Int)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed filter expression such as
+/// `type == "Overdue" && owner in ["alice", "bob"] && path ~ "^src/api"`.
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    Matches(Box<Expr>, Regex),
+    In(Box<Expr>, Box<Expr>),
+    List(Vec<Expr>),
+    Field(String),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Op(&'static str),
+}
+
+/// Operators and punctuation, longest first so that `<=` isn't read as `<`.
+const OPERATORS: [&str; 15] = [
+    "&&", "||", "==", "!=", "<=", ">=", "!~", "<", ">", "~", "!", "(", ")", "[", "]",
+];
+const COMMA: &str = ",";
+
+impl Expr {
+    /// Parses an expression, rejecting fields that aren't in `fields`.
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Expr> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            fields,
+        };
+        let expr = parser.parse_or()?;
+        match parser.tokens.get(parser.position) {
+            Some((token, column)) => Err(anyhow!(
+                "Unexpected {} at column {}",
+                describe(token),
+                column
+            )),
+            None => Ok(expr),
+        }
+    }
+
+    /// Evaluates the expression, looking up fields with `field`.
+    pub fn matches(&self, field: &impl Fn(&str) -> Value) -> bool {
+        self.evaluate(field).is_truthy()
+    }
+
+    fn evaluate(&self, field: &impl Fn(&str) -> Value) -> Value {
+        let result = match self {
+            Expr::And(left, right) => left.matches(field) && right.matches(field),
+            Expr::Or(left, right) => left.matches(field) || right.matches(field),
+            Expr::Not(expr) => !expr.matches(field),
+            Expr::Compare(left, op, right) => {
+                compare(&left.evaluate(field), *op, &right.evaluate(field))
+            }
+            Expr::Matches(expr, regex) => match expr.evaluate(field) {
+                Value::Str(s) => regex.is_match(&s),
+                _ => false,
+            },
+            Expr::In(needle, haystack) => {
+                match (needle.evaluate(field), haystack.evaluate(field)) {
+                    (needle, Value::List(items)) => items.contains(&needle),
+                    (Value::Str(needle), Value::Str(haystack)) => haystack.contains(&needle),
+                    _ => false,
+                }
+            }
+            Expr::List(items) => {
+                return Value::List(items.iter().map(|item| item.evaluate(field)).collect())
+            }
+            Expr::Field(name) => return field(name),
+            Expr::Literal(value) => return value.clone(),
+        };
+        Value::Bool(result)
+    }
+}
+
+/// Compares values of the same type, dates being compared as `YYYY-MM-DD` strings.
+/// Ordering comparisons with `null` or values of different types are false.
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match op {
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+        CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+        CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+    }
+}
+
+/// Splits the source into tokens along with their 1-based column.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    Some('"') => break,
+                    Some('\\') => {
+                        match chars.get(i + 1) {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(&escaped) => value.push(escaped),
+                            None => bail!("Unterminated string at column {}", column),
+                        }
+                        i += 2;
+                    }
+                    Some(&c) => {
+                        value.push(c);
+                        i += 1;
+                    }
+                    None => bail!("Unterminated string at column {}", column),
+                }
+            }
+            i += 1;
+            tokens.push((Token::Str(value), column));
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            let start = i;
+            i += 1;
+            while chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            let number = number
+                .parse()
+                .map_err(|_| anyhow!("Invalid number {} at column {}", number, column))?;
+            tokens.push((Token::Int(number), column));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while chars
+                .get(i)
+                .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+            {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), column));
+        } else if c == ',' {
+            tokens.push((Token::Op(COMMA), column));
+            i += 1;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => {
+                    tokens.push((Token::Op(op), column));
+                    i += op.chars().count();
+                }
+                None if c == '=' => {
+                    bail!("Unexpected '=' at column {}, use '==' to compare", column)
+                }
+                None => bail!("Unexpected character '{}' at column {}", c, column),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("\"{}\"", name),
+        Token::Str(value) => format!("string \"{}\"", value),
+        Token::Int(number) => format!("number {}", number),
+        Token::Op(op) => format!("'{}'", op),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    fields: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<(Token, usize)> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of the filter"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consumes the operator if it comes next.
+    fn eat(&mut self, op: &'static str) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<()> {
+        let (token, column) = self.next()?;
+        if token == Token::Op(op) {
+            Ok(())
+        } else {
+            bail!(
+                "Expected '{}' at column {}, found {}",
+                op,
+                column,
+                describe(&token)
+            )
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_not()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let left = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            Some(Token::Ident(name)) if name == "in" => "in",
+            _ => return Ok(left),
+        };
+        let compare_op = match op {
+            "==" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            "~" | "!~" => {
+                self.position += 1;
+                let (token, column) = self.next()?;
+                let pattern = match token {
+                    Token::Str(pattern) => pattern,
+                    other => bail!(
+                        "Expected a regex string after '{}' at column {}, found {}",
+                        op,
+                        column,
+                        describe(&other)
+                    ),
+                };
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| anyhow!("Invalid regex at column {}: {}", column, e))?;
+                let matches = Expr::Matches(Box::new(left), regex);
+                return Ok(if op == "~" {
+                    matches
+                } else {
+                    Expr::Not(Box::new(matches))
+                });
+            }
+            "in" => {
+                self.position += 1;
+                return Ok(Expr::In(Box::new(left), Box::new(self.parse_primary()?)));
+            }
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Expr::Compare(
+            Box::new(left),
+            compare_op,
+            Box::new(self.parse_primary()?),
+        ))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let (token, column) = self.next()?;
+        match token {
+            Token::Str(value) => Ok(Expr::Literal(Value::Str(value))),
+            Token::Int(number) => Ok(Expr::Literal(Value::Int(number))),
+            Token::Op("(") => {
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Op("[") => {
+                let mut items = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.parse_primary()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(COMMA)?;
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.fields.contains(&name.as_str()) => Ok(Expr::Field(name)),
+                _ => bail!(
+                    "Unknown field \"{}\" at column {}{}",
+                    name,
+                    column,
+                    did_you_mean(suggest(&name, self.fields.iter().copied()))
+                ),
+            },
+            other => bail!("Unexpected {} at column {}", describe(&other), column),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cli::{Cli, WARNING_TYPES};
+use crate::config::{Config, RuleSettings, Severities};
+use crate::expr::{Expr, Value};
+use crate::git;
+use crate::todo_analyzer::TodoWarning;
+use crate::validate::{did_you_mean, suggest};
+use anyhow::anyhow;
+use std::path::{Path, PathBuf};
+
+/// The fields of a warning that filter expressions can use.
+pub const FIELDS: [&str; 16] = [
+    "type",
+    "severity",
+    "path",
+    "line",
+    "owner",
+    "due_date",
+    "due_name",
+    "days_overdue",
+    "days_until_due",
+    "business_days_until_due",
+    "age_days",
+    "implicit_due_date",
+    "comment",
+    "policy",
+    "team",
+    "labels",
+];
+
+/// The `--owner`, `--type`, `--path-prefix`, `--mine`, `--filter` and `--view` filters.
+/// Empty lists match everything.
+#[derive(Debug)]
+pub struct WarningFilter {
+    owners: Vec<String>,
+    types: Vec<&'static str>,
+    path_prefixes: Vec<PathBuf>,
+    expressions: Vec<Expr>,
+    severities: Severities,
+}
+
+impl WarningFilter {
+    /// Builds the filter from the command-line options, resolving `--mine` with the git
+    /// config of the repository containing `base_dir`.
+    pub fn new(cli: &Cli, config: &Config, base_dir: &Path) -> anyhow::Result<Self> {
+        let mut owners: Vec<String> = cli
+            .owner
+            .iter()
+            .map(|owner| owner.trim_start_matches('@').to_string())
+            .collect();
+        if cli.mine {
+            owners.extend(current_owners(config, base_dir)?);
+        }
+
+        let types = WARNING_TYPES
+            .iter()
+            .filter(|(name, _)| cli.types.iter().any(|t| t == name))
+            .map(|(_, type_name)| *type_name)
+            .collect();
+
+        let mut expressions = Vec::new();
+        for name in &cli.view {
+            let view = config.views.get(name).ok_or_else(|| {
+                anyhow!(
+                    "Unknown view \"{}\"{}",
+                    name,
+                    did_you_mean(suggest(name, config.views.keys().map(String::as_str)))
+                )
+            })?;
+            expressions.push(
+                Expr::parse(&view.filter, &FIELDS)
+                    .map_err(|e| anyhow!("Invalid filter of view \"{}\": {}", name, e))?,
+            );
+        }
+        for filter in &cli.filter {
+            expressions.push(
+                Expr::parse(filter, &FIELDS).map_err(|e| anyhow!("Invalid --filter: {}", e))?,
+            );
+        }
+
+        Ok(WarningFilter {
+            owners,
+            types,
+            path_prefixes: cli.path_prefix.iter().map(PathBuf::from).collect(),
+            expressions,
+            severities: config.severity.clone(),
+        })
+    }
+
+    /// Whether a file, relative to the config file, is under one of the path prefixes.
+    pub fn matches_path(&self, file_path: &str) -> bool {
+        self.path_prefixes.is_empty()
+            || self
+                .path_prefixes
+                .iter()
+                .any(|prefix| Path::new(file_path).starts_with(prefix))
+    }
+
+    /// Whether any filter applies to the warnings themselves rather than to their files.
+    pub fn filters_warnings(&self) -> bool {
+        !self.owners.is_empty() || !self.types.is_empty() || !self.expressions.is_empty()
+    }
+
+    pub fn matches(&self, file_path: &str, settings: &RuleSettings, warning: &TodoWarning) -> bool {
+        let owner_matches = self.owners.is_empty()
+            || warning
+                .owner()
+                .is_some_and(|owner| self.owners.iter().any(|o| o == owner));
+        let type_matches = self.types.is_empty() || self.types.contains(&warning.type_name());
+        let field = |name: &str| self.field(name, file_path, settings, warning);
+        owner_matches && type_matches && self.expressions.iter().all(|expr| expr.matches(&field))
+    }
+
+    /// Looks up a field of `FIELDS`, with the same values as the JSON output, except for
+    /// due dates written as weeks, months, quarters or years, which are their last day.
+    fn field(
+        &self,
+        name: &str,
+        file_path: &str,
+        settings: &RuleSettings,
+        warning: &TodoWarning,
+    ) -> Value {
+        match name {
+            "type" => Value::Str(warning.type_name().to_string()),
+            "severity" => Value::Str(warning.severity(&self.severities).as_str().to_string()),
+            "path" => Value::Str(file_path.to_string()),
+            "line" => Value::Int(warning.line_number() as i64),
+            "owner" => warning.owner().map(str::to_string).into(),
+            "due_date" => warning
+                .due_date()
+                .map(|due_date| due_date.format("%Y-%m-%d").to_string())
+                .into(),
+            "due_name" => warning.due_name().map(str::to_string).into(),
+            "days_overdue" => match warning {
+                TodoWarning::Overdue { days_overdue, .. } => Value::Int(*days_overdue),
+                _ => Value::Null,
+            },
+            "days_until_due" => match warning {
+                TodoWarning::DueSoon { days_until_due, .. } => Value::Int(*days_until_due),
+                _ => Value::Null,
+            },
+            "business_days_until_due" => match warning {
+                TodoWarning::DueSoon {
+                    business_days_until_due,
+                    ..
+                } => (*business_days_until_due).into(),
+                _ => Value::Null,
+            },
+            "age_days" => warning.age_days().into(),
+            "implicit_due_date" => Value::Bool(warning.has_implicit_due_date()),
+            "comment" => Value::Str(warning.comment().to_string()),
+            "policy" => match warning {
+                TodoWarning::PolicyViolation { policy, .. } => Value::Str(policy.to_string()),
+                _ => Value::Null,
+            },
+            "team" => settings.team.clone().into(),
+            "labels" => Value::List(
+                settings
+                    .labels
+                    .iter()
+                    .map(|label| Value::Str(label.clone()))
+                    .collect(),
+            ),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Resolves the owners of the current git user from their email and name.
+fn current_owners(config: &Config, base_dir: &Path) -> anyhow::Result<Vec<String>> {
+    let identities: Vec<String> = ["user.email", "user.name"]
+        .into_iter()
+        .filter_map(|key| git::config_value(base_dir, key))
+        .collect();
+    if identities.is_empty() {
+        return Err(anyhow!(
+            "--mine needs git user.email or user.name to be set"
+        ));
+    }
+    Ok(config.owners_of(&identities))
+}
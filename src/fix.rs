@@ -0,0 +1,183 @@
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::git::{self, BlameLine};
+use crate::hint::LooseTodo;
+use crate::todo_analyzer::{TodoAnalyzer, TodoItem};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use similar::TextDiff;
+use std::ops::Range;
+use std::path::Path;
+
+/// Rewrites TODOs with an invalid format into the `TODO: YYYY-MM-DD @owner text` form for
+/// `--fix`.
+pub struct Fixer {
+    default_owner: Option<String>,
+    default_due: NaiveDate,
+    dry_run: bool,
+    fixed_todos: usize,
+    fixed_files: usize,
+    /// TODOs that couldn't be fixed, with the reason.
+    skipped: Vec<String>,
+}
+
+impl Fixer {
+    pub fn new(cli: &Cli, today: NaiveDate) -> Result<Self> {
+        Ok(Fixer {
+            default_owner: cli
+                .default_owner
+                .as_ref()
+                .map(|owner| owner.trim_start_matches('@').to_string()),
+            default_due: cli.default_due_date(today).map_err(|e| anyhow!(e))?,
+            dry_run: cli.dry_run,
+            fixed_todos: 0,
+            fixed_files: 0,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Fixes the TODOs of a file with an invalid format.
+    pub fn fix_file(
+        &mut self,
+        analyzer: &TodoAnalyzer,
+        config: &Config,
+        path: &Path,
+        relative_path: &str,
+        content: &str,
+        todos: &[TodoItem],
+    ) -> Result<()> {
+        let invalid: Vec<&TodoItem> = todos.iter().filter(|todo| !todo.is_valid_format).collect();
+        if invalid.is_empty() {
+            return Ok(());
+        }
+
+        // Files outside of a git repository only get the default owner
+        let blame = git::blame_lines(path).unwrap_or_default();
+        let mut edits = Vec::new();
+        for todo in invalid {
+            match self.fix_comment(analyzer, config, content, todo, &blame) {
+                Ok(edit) => edits.push(edit),
+                Err(reason) => self.skipped.push(format!(
+                    "{}:{}: {}",
+                    relative_path, todo.line_number, reason
+                )),
+            }
+        }
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        self.fixed_todos += edits.len();
+        self.fixed_files += 1;
+        apply_edits(path, relative_path, content, edits, self.dry_run)
+    }
+
+    /// Returns the byte range of the line of the comment with the TODO keyword, along with
+    /// its replacement. Existing dates, owners and text are kept.
+    fn fix_comment(
+        &self,
+        analyzer: &TodoAnalyzer,
+        config: &Config,
+        content: &str,
+        todo: &TodoItem,
+        blame: &[BlameLine],
+    ) -> Result<(Range<usize>, String), String> {
+        let comment = &content[todo.byte_range.clone()];
+        let loose = LooseTodo::parse(analyzer, comment)?;
+        if let Some(problem) = loose
+            .ambiguous_date
+            .as_ref()
+            .or(loose.invalid_date.as_ref())
+        {
+            return Err(problem.clone());
+        }
+        let line_start = loose.line_range.start;
+
+        let line_number = todo.line_number + comment[..line_start].matches('\n').count();
+        let (owner, text) = loose.owner_and_text(false);
+        let owner = owner
+            .map(str::to_string)
+            .or_else(|| blame_owner(config, blame.get(line_number - 1)?))
+            .or_else(|| self.default_owner.clone())
+            .ok_or("No owner found in git blame, use --default-owner")?;
+        let due_date = loose
+            .due_date
+            .clone()
+            .unwrap_or_else(|| analyzer.date_formats().write(self.default_due, None));
+
+        let line = loose.rewrite(&due_date, &owner, text);
+        if !analyzer.is_valid_format(&line) {
+            return Err(format!("Can't rewrite \"{}\"", comment.trim()));
+        }
+
+        let start = todo.byte_range.start;
+        Ok((
+            start + loose.line_range.start..start + loose.line_range.end,
+            line,
+        ))
+    }
+
+    /// Prints the TODOs that couldn't be fixed and a summary. Both go to stderr, so that
+    /// the diff of `--dry-run` can be piped into a file.
+    pub fn print_summary(&mut self) {
+        self.skipped.sort();
+        for skipped in &self.skipped {
+            eprintln!("Skipped {}", skipped);
+        }
+        eprintln!(
+            "{} {} TODO{} in {} file{}",
+            if self.dry_run { "Would fix" } else { "Fixed" },
+            self.fixed_todos,
+            if self.fixed_todos == 1 { "" } else { "s" },
+            self.fixed_files,
+            if self.fixed_files == 1 { "" } else { "s" }
+        );
+    }
+}
+
+/// Replaces byte ranges of a file, writing it back or printing the changes as a unified diff
+/// with `dry_run`.
+pub fn apply_edits(
+    path: &Path,
+    relative_path: &str,
+    content: &str,
+    mut edits: Vec<(Range<usize>, String)>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut edited = content.to_string();
+    edits.sort_by_key(|(range, _)| range.start);
+    for (range, text) in edits.iter().rev() {
+        edited.replace_range(range.clone(), text);
+    }
+
+    if dry_run {
+        print!(
+            "{}",
+            TextDiff::from_lines(content, &edited)
+                .unified_diff()
+                .header(
+                    &format!("a/{}", relative_path),
+                    &format!("b/{}", relative_path)
+                )
+        );
+    } else {
+        std::fs::write(path, edited)?;
+    }
+    Ok(())
+}
+
+/// The owner of the git author of a line, if it is committed and maps to a valid owner.
+fn blame_owner(config: &Config, line: &BlameLine) -> Option<String> {
+    if !line.is_committed() {
+        return None;
+    }
+    config
+        .owners_of(&[line.author_email.clone(), line.author_name.clone()])
+        .into_iter()
+        .find(|owner| {
+            !owner.is_empty()
+                && owner
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "_.-".contains(c))
+        })
+}
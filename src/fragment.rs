@@ -0,0 +1,68 @@
+use crate::config::{Fragment, RuleSettings};
+use std::path::{Path, PathBuf};
+
+struct Layer {
+    dir: PathBuf,
+    settings: RuleSettings,
+    excludes: Vec<PathBuf>,
+}
+
+/// The fragments that apply at the current position of a depth-first directory walk, layered
+/// on top of the settings of the rule.
+pub struct FragmentStack {
+    base: RuleSettings,
+    excludes: Vec<PathBuf>,
+    layers: Vec<Layer>,
+}
+
+impl FragmentStack {
+    /// `excludes` are the absolute paths excluded by the rule.
+    pub fn new(base: RuleSettings, excludes: Vec<PathBuf>) -> Self {
+        Self {
+            base,
+            excludes,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Drops the fragments of the directories that the walk has left before reaching `path`.
+    pub fn leave(&mut self, path: &Path) {
+        while let Some(layer) = self.layers.last() {
+            if path.starts_with(&layer.dir) {
+                break;
+            }
+            self.layers.pop();
+        }
+    }
+
+    /// Applies the fragment of a directory the walk enters. Returns whether the directory
+    /// should be walked, which is not the case for disabled fragments.
+    pub fn enter(&mut self, dir: &Path, fragment: &Fragment) -> bool {
+        if fragment.disable {
+            return false;
+        }
+
+        let mut settings = self.settings().clone();
+        settings.apply_fragment(fragment);
+        self.layers.push(Layer {
+            dir: dir.to_path_buf(),
+            settings,
+            excludes: fragment.exclude.iter().map(|e| dir.join(e)).collect(),
+        });
+        true
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excludes
+            .iter()
+            .chain(self.layers.iter().flat_map(|layer| &layer.excludes))
+            .any(|exclude| path.starts_with(exclude))
+    }
+
+    /// The settings of the innermost fragment, without the command-line overrides.
+    pub fn settings(&self) -> &RuleSettings {
+        self.layers
+            .last()
+            .map_or(&self.base, |layer| &layer.settings)
+    }
+}
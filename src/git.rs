@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, TimeZone};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the top-level directory of the git repository containing `dir`.
+pub fn repo_root(dir: &Path) -> Result<PathBuf> {
+    let output = run_git(dir, &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+/// Returns a value of the git config as seen from `dir`, or `None` if it isn't set.
+pub fn config_value(dir: &Path, key: &str) -> Option<String> {
+    run_git(dir, &["config", "--get", key])
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Lists all files tracked at `rev`, relative to the repository root.
+pub fn list_files(repo_root: &Path, rev: &str) -> Result<Vec<String>> {
+    let output = run_git(repo_root, &["ls-tree", "-r", "-z", "--name-only", rev])?;
+    Ok(output
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// Reads the content of `path` (relative to the repository root) at `rev`.
+pub fn show_file(repo_root: &Path, rev: &str, path: &str) -> Result<String> {
+    run_git(repo_root, &["show", &format!("{}:{}", rev, path)])
+}
+
+/// The last change of a line, according to `git blame`.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    pub commit: String,
+    /// When the change was committed, which is when it landed rather than when it was first
+    /// written on a branch that was later rebased.
+    pub time: DateTime<FixedOffset>,
+    pub author_name: String,
+    pub author_email: String,
+}
+
+impl BlameLine {
+    /// Lines that aren't committed yet are attributed to an all-zero commit.
+    pub fn is_committed(&self) -> bool {
+        self.commit.bytes().any(|b| b != b'0')
+    }
+}
+
+/// Returns when and by whom each line of the working tree file was last changed, according
+/// to `git blame`. Uncommitted lines are attributed to the time of the blame.
+pub fn blame_lines(path: &Path) -> Result<Vec<BlameLine>> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("Invalid file path: {}", path.display()))?;
+    let output = run_git(
+        dir,
+        &["blame", "-w", "-M", "--line-porcelain", "--", file_name],
+    )?;
+
+    let mut lines = Vec::new();
+    let (mut commit, mut author_name, mut author_email) =
+        (String::new(), String::new(), String::new());
+    let mut timestamp = 0;
+    let mut header = true;
+    for line in output.lines() {
+        if header {
+            // Each line starts with the commit, followed by the line numbers
+            commit = line.split(' ').next().unwrap_or_default().to_string();
+            header = false;
+        } else if line.starts_with('\t') {
+            // The content of the line ends its entry
+            header = true;
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author_name = name.to_string();
+        } else if let Some(email) = line.strip_prefix("author-mail ") {
+            author_email = email.trim_matches(|c| c == '<' || c == '>').to_string();
+        } else if let Some(time) = line.strip_prefix("committer-time ") {
+            timestamp = time.parse()?;
+        } else if let Some(tz) = line.strip_prefix("committer-tz ") {
+            let offset =
+                parse_tz(tz).ok_or_else(|| anyhow!("Invalid timezone in git blame: {}", tz))?;
+            let time = offset
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .ok_or_else(|| anyhow!("Invalid timestamp in git blame: {}", timestamp))?;
+            lines.push(BlameLine {
+                commit: std::mem::take(&mut commit),
+                time,
+                author_name: std::mem::take(&mut author_name),
+                author_email: std::mem::take(&mut author_email),
+            });
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Parses a git timezone like `+0200` or `-0530`.
+fn parse_tz(tz: &str) -> Option<FixedOffset> {
+    let (sign, digits) = match tz.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
@@ -0,0 +1,317 @@
+use crate::calendar::CALENDAR_NAME_PATTERN;
+use crate::dates::{parse_partial_date, DateError, DateFormats};
+use crate::todo_analyzer::TodoAnalyzer;
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+use std::fmt;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// The closing delimiters of block comments and docstrings, which stay after the text.
+const COMMENT_CLOSERS: [&str; 4] = ["*/", "-->", "\"\"\"", "'''"];
+
+/// Dates with the year first, e.g. `2024-05-01`, `2024/05/01` or `2024.5.1`.
+static YEAR_FIRST_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})\b").unwrap());
+/// Dates with the year last, e.g. `05-01-2024` or `01/05/2024`.
+static YEAR_LAST_DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{1,2})[-/.](\d{1,2})[-/.](\d{4})\b").unwrap());
+/// Weeks, months, quarters and years, e.g. `2025-W14`, `2025-03`, `2025-Q2` or `2025`.
+static PARTIAL_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}(?:-(?:\d{1,2}|[Qq]\d|[Ww]\d{1,2}))?)(?:\s|$|[,)\]])").unwrap()
+});
+static CALENDAR_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"^({})(?:\s|$|[,)\]])", CALENDAR_NAME_PATTERN)).unwrap());
+static OWNER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^@([\w.-]+)").unwrap());
+/// A word after the date that may be an owner written without the `@`.
+static BARE_OWNER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Za-z][\w.-]*)(:|\s|$)").unwrap());
+
+/// What's wrong with a TODO with an invalid format, and the comment it probably meant.
+#[derive(Debug, Clone)]
+pub struct FormatHint {
+    pub problems: Vec<String>,
+    pub suggestion: String,
+}
+
+impl fmt::Display for FormatHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for problem in &self.problems {
+            write!(f, "{}. ", problem)?;
+        }
+        write!(f, "Did you mean \"{}\"?", self.suggestion)
+    }
+}
+
+/// A TODO comment with an invalid format, taken apart as far as possible.
+#[derive(Debug)]
+pub struct LooseTodo<'a> {
+    /// The byte range of the line with the keyword in the comment.
+    pub line_range: Range<usize>,
+    /// The comment delimiters before the keyword.
+    pub prefix: &'a str,
+    /// The configured keyword that matched.
+    pub keyword: &'a str,
+    /// The due date in one of the configured date formats.
+    pub due_date: Option<String>,
+    pub owner: Option<String>,
+    /// The text after the date and the owner.
+    pub text: &'a str,
+    /// The comment delimiters after the text.
+    pub suffix: &'a str,
+    /// What keeps the comment from having a valid format.
+    pub problems: Vec<String>,
+    /// Why the date can be read as more than one day, in which case `due_date` is only the
+    /// first reading.
+    pub ambiguous_date: Option<String>,
+    /// Why the date isn't a valid day, like `2024-13-45`.
+    pub invalid_date: Option<String>,
+    bare_owner: Option<&'a str>,
+}
+
+impl<'a> LooseTodo<'a> {
+    /// Takes apart the line of a comment with the TODO keyword. Fails for comments that
+    /// only mention the keyword in their text.
+    pub fn parse(analyzer: &'a TodoAnalyzer, comment: &'a str) -> Result<Self, String> {
+        let (keyword_range, keyword) = analyzer
+            .find_keyword(comment)
+            .ok_or("No TODO keyword found")?;
+        let line_start = comment[..keyword_range.start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let prefix = &comment[line_start..keyword_range.start];
+        if prefix.chars().any(char::is_alphanumeric) {
+            return Err("The TODO keyword isn't at the start of the comment".to_string());
+        }
+        let line_end = comment[keyword_range.end..]
+            .find('\n')
+            .map_or(comment.len(), |i| keyword_range.end + i);
+
+        let rest = &comment[keyword_range.end..line_end];
+        let trimmed = rest.trim_end();
+        let body_end = COMMENT_CLOSERS
+            .iter()
+            .find(|closer| trimmed.ends_with(*closer))
+            .map_or(trimmed.len(), |closer| {
+                trimmed[..trimmed.len() - closer.len()].trim_end().len()
+            });
+        let (mut body, suffix) = rest.split_at(body_end);
+
+        let mut problems = Vec::new();
+        if let Some(format) = analyzer.disabled_format(comment) {
+            problems.push(format!(
+                "The {} format isn't enabled in the formats setting",
+                format.as_str()
+            ));
+        }
+        let mut has_colon = comment[keyword_range].ends_with(':');
+        let mut owner = None;
+        let mut due_date = None;
+        let mut invalid_date = None;
+        // The TODO(owner) convention
+        if let Some((inner, after)) = body
+            .strip_prefix('(')
+            .and_then(|inner| inner.split_once(')'))
+        {
+            let inner = inner.trim().trim_start_matches('@');
+            if !inner.is_empty() {
+                problems.push(format!(
+                    "Write the owner as @{} after the date, not in parentheses",
+                    inner
+                ));
+                owner = Some(inner.to_string());
+            }
+            body = after;
+            has_colon = has_colon || body.starts_with(':');
+        }
+        if !has_colon {
+            problems.push(format!("Missing colon after {}", keyword));
+        }
+        body = body.trim_start_matches(|c: char| c == ':' || c == '-' || c.is_whitespace());
+
+        let formats = analyzer.date_formats();
+        let mut owner_before_date = false;
+        let mut ambiguous_date = None;
+        loop {
+            if due_date.is_none() && invalid_date.is_none() {
+                if let Some((length, written, date)) = parse_date(formats, body) {
+                    let normalized = match &date {
+                        Ok(normalized) => {
+                            if written != normalized {
+                                problems
+                                    .push(format!("Write the date {} as {}", written, normalized));
+                            }
+                            Some(normalized.clone())
+                        }
+                        Err(error @ DateError::Ambiguous(dates)) => {
+                            let problem = error.describe(written);
+                            problems.push(problem.clone());
+                            ambiguous_date = Some(problem);
+                            Some(formats.write(dates[0], None))
+                        }
+                        Err(error) => {
+                            // Skip the date to read the owner after it
+                            let problem = error.describe(written);
+                            problems.push(problem.clone());
+                            invalid_date = Some(problem);
+                            body = body[length..].trim_start();
+                            continue;
+                        }
+                    };
+                    if let Some(normalized) = normalized {
+                        if owner_before_date {
+                            problems.push("The due date must come before the owner".to_string());
+                        }
+                        due_date = Some(normalized);
+                        body = body[length..].trim_start();
+                        continue;
+                    }
+                }
+            }
+            if owner.is_none() {
+                if let Some(captures) = OWNER.captures(body) {
+                    owner = Some(captures[1].to_string());
+                    owner_before_date = due_date.is_none();
+                    body = body[captures[0].len()..].trim_start();
+                    continue;
+                }
+            }
+            break;
+        }
+
+        let mut bare_owner = None;
+        if due_date.is_none() && invalid_date.is_none() {
+            problems.push("Missing due date (YYYY-MM-DD)".to_string());
+        }
+        if owner.is_none() {
+            bare_owner = BARE_OWNER
+                .captures(body)
+                .filter(|_| due_date.is_some())
+                .map(|captures| captures.get(1).unwrap().as_str());
+            problems.push(match bare_owner {
+                Some(bare_owner) => format!("Missing @ before the owner {}", bare_owner),
+                None => "Missing @owner".to_string(),
+            });
+        }
+
+        Ok(LooseTodo {
+            line_range: line_start..line_end,
+            prefix,
+            keyword,
+            due_date,
+            owner,
+            text: body,
+            suffix,
+            problems,
+            ambiguous_date,
+            invalid_date,
+            bare_owner,
+        })
+    }
+
+    /// The line in the `TODO: YYYY-MM-DD @owner text` format.
+    pub fn rewrite(&self, due_date: &str, owner: &str, text: &str) -> String {
+        let mut line = format!("{}{}: {} @{}", self.prefix, self.keyword, due_date, owner);
+        if !text.is_empty() {
+            line.push(' ');
+            line.push_str(text);
+        }
+        line.push_str(self.suffix);
+        line
+    }
+
+    /// The line as it was probably meant, with placeholders for a missing due date or
+    /// owner.
+    pub fn suggestion(&self) -> String {
+        let (owner, text) = self.owner_and_text(true);
+        self.rewrite(
+            self.due_date.as_deref().unwrap_or("YYYY-MM-DD"),
+            owner.unwrap_or("owner"),
+            text,
+        )
+        .trim()
+        .to_string()
+    }
+
+    /// The owner and the text after it. A word after the date is taken as an owner written
+    /// without the @ if it is followed by a colon, like `bob: text`, or with `guess` for any
+    /// word.
+    pub fn owner_and_text(&self, guess: bool) -> (Option<&str>, &str) {
+        match (&self.owner, self.bare_owner) {
+            (Some(owner), _) => (Some(owner.as_str()), self.text),
+            (None, Some(bare_owner)) => {
+                let rest = &self.text[bare_owner.len()..];
+                match rest.strip_prefix(':') {
+                    Some(rest) => (Some(bare_owner), rest.trim_start()),
+                    None if guess => (Some(bare_owner), rest.trim_start()),
+                    None => (None, self.text),
+                }
+            }
+            (None, None) => (None, self.text),
+        }
+    }
+
+    pub fn hint(&self) -> FormatHint {
+        FormatHint {
+            problems: self.problems.clone(),
+            suggestion: self.suggestion(),
+        }
+    }
+}
+
+/// Reads a date at the start of the text, returning its length, how it was written and
+/// the date as it should be written, or why it can't be read. Dates in one of the formats
+/// and the names of sprints and milestones are kept as they are. Others are written in one
+/// of the formats, reading dates with the year last as month first if the first number can
+/// be a month.
+fn parse_date<'t>(
+    formats: &DateFormats,
+    text: &'t str,
+) -> Option<(usize, &'t str, Result<String, DateError>)> {
+    if let Some(written) = formats.find(text) {
+        let date = formats.parse(written).map(|_| written.to_string());
+        return Some((written.len(), written, date));
+    }
+
+    if let Some(name) = CALENDAR_NAME.captures(text) {
+        let written = name.get(1)?.as_str();
+        return Some((written.len(), written, Ok(written.to_string())));
+    }
+
+    let number = |captures: &Captures, i: usize| captures[i].parse::<u32>().unwrap_or(0);
+    let write = |date: Option<NaiveDate>| {
+        date.map(|date| formats.write(date, None))
+            .ok_or(DateError::Invalid)
+    };
+    if let Some(captures) = YEAR_FIRST_DATE.captures(text) {
+        let date = NaiveDate::from_ymd_opt(
+            number(&captures, 1) as i32,
+            number(&captures, 2),
+            number(&captures, 3),
+        );
+        let written = captures.get(0).unwrap().as_str();
+        return Some((written.len(), written, write(date)));
+    }
+    if let Some(captures) = YEAR_LAST_DATE.captures(text) {
+        let (first, second) = (number(&captures, 1), number(&captures, 2));
+        let year = number(&captures, 3) as i32;
+        let written = captures.get(0).unwrap().as_str();
+        let date = if first > 12 {
+            write(NaiveDate::from_ymd_opt(year, second, first))
+        } else if second > 12 || first == second {
+            write(NaiveDate::from_ymd_opt(year, first, second))
+        } else {
+            let month_first = NaiveDate::from_ymd_opt(year, first, second);
+            let day_first = NaiveDate::from_ymd_opt(year, second, first);
+            Err(DateError::Ambiguous(
+                month_first.into_iter().chain(day_first).collect(),
+            ))
+        };
+        return Some((written.len(), written, date));
+    }
+    let written = PARTIAL_DATE.captures(text)?.get(1)?.as_str();
+    let date = parse_partial_date(written)
+        .map(|(date, precision)| precision.format(date.into(), "%Y-%m-%d"))
+        .ok_or(DateError::Invalid);
+    Some((written.len(), written, date))
+}
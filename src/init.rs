@@ -0,0 +1,165 @@
+use crate::cli::InitArgs;
+use crate::config::DEFAULT_CONFIG_FILE_NAME;
+use crate::languages::get_language_configs;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Component, Path};
+use walkdir::WalkDir;
+
+/// Directories of dependencies and build output, which are excluded from the rules.
+const EXCLUDED_DIRS: [&str; 3] = ["target", "node_modules", "vendor"];
+
+/// The files of a language found in the repository.
+#[derive(Default)]
+struct Detected {
+    file_count: usize,
+    top_level_dirs: BTreeSet<String>,
+}
+
+/// Writes a `todo-reminder.toml` with a rule for each language found in the current
+/// directory.
+pub fn run(args: &InitArgs) -> anyhow::Result<()> {
+    let config_file_path = Path::new(DEFAULT_CONFIG_FILE_NAME);
+    if config_file_path.exists() && !args.force {
+        anyhow::bail!(
+            "{} already exists, use --force to overwrite it",
+            DEFAULT_CONFIG_FILE_NAME
+        );
+    }
+
+    let language_by_extension: BTreeMap<&str, String> = get_language_configs()
+        .into_iter()
+        .flat_map(|(name, language_config)| {
+            language_config
+                .file_extensions
+                .into_iter()
+                .map(move |extension| (extension, name.clone()))
+        })
+        .collect();
+
+    let mut detected: BTreeMap<String, Detected> = BTreeMap::new();
+    let mut excluded_dirs: Vec<String> = Vec::new();
+
+    let mut entries = WalkDir::new(".")
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            // Skip hidden directories such as .git
+            !(entry.file_type().is_dir() && entry.file_name().to_string_lossy().starts_with('.'))
+        });
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let path = entry.path().strip_prefix(".")?;
+        let name = entry.file_name().to_string_lossy();
+
+        if entry.file_type().is_dir() {
+            if EXCLUDED_DIRS.contains(&name.as_ref()) {
+                // Excluded directories at the top level are never part of a rule
+                if entry.depth() > 1 {
+                    excluded_dirs.push(to_config_path(path));
+                }
+                entries.skip_current_dir();
+            }
+            continue;
+        }
+
+        // Files at the top level are skipped, since rules scan directories
+        let top_level_dir = match path.components().next() {
+            Some(Component::Normal(dir)) if entry.depth() > 1 => dir.to_string_lossy(),
+            _ => continue,
+        };
+        let language = match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| language_by_extension.get(ext))
+        {
+            Some(language) => language,
+            None => continue,
+        };
+
+        let detected = detected.entry(language.clone()).or_default();
+        detected.file_count += 1;
+        detected.top_level_dirs.insert(top_level_dir.to_string());
+    }
+
+    if detected.is_empty() {
+        anyhow::bail!("No files of a supported language found in subdirectories");
+    }
+
+    let mut languages: Vec<(&String, &Detected)> = detected.iter().collect();
+    languages.sort_by(|a, b| b.1.file_count.cmp(&a.1.file_count).then(a.0.cmp(b.0)));
+
+    let mut config = String::from(
+        "# Generated by `todo-reminder init`. Run `todo-reminder validate-config` after editing\n\
+         # this file, and see config.example.toml in the todo-reminder repository for all settings.\n\
+         \n\
+         [parameters]\n\
+         # The URL to open the file in the editor\n\
+         editor_url = \"vscode://file/%%file%%:%%line%%\"\n\
+         # Report TODOs that are due within this many days\n\
+         # due_in = 7\n",
+    );
+
+    for (language, detected) in &languages {
+        let excludes: Vec<&String> = excluded_dirs
+            .iter()
+            .filter(|dir| {
+                detected
+                    .top_level_dirs
+                    .iter()
+                    .any(|top_level_dir| Path::new(dir).starts_with(top_level_dir))
+            })
+            .collect();
+
+        writeln!(config)?;
+        writeln!(
+            config,
+            "# {} file{} found",
+            detected.file_count,
+            if detected.file_count == 1 { "" } else { "s" }
+        )?;
+        writeln!(config, "[[rules]]")?;
+        writeln!(config, "paths = {}", toml_array(&detected.top_level_dirs))?;
+        writeln!(
+            config,
+            "language = {}",
+            toml::Value::from(language.as_str())
+        )?;
+        if !excludes.is_empty() {
+            writeln!(config, "# Dependencies and build output")?;
+            writeln!(config, "exclude = {}", toml_array(excludes))?;
+        }
+    }
+
+    std::fs::write(config_file_path, config)?;
+    println!(
+        "Created {} with {} rule{} for {}",
+        DEFAULT_CONFIG_FILE_NAME,
+        languages.len(),
+        if languages.len() == 1 { "" } else { "s" },
+        languages
+            .iter()
+            .map(|(language, _)| language.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    Ok(())
+}
+
+/// Formats a path with forward slashes, as config files are shared across platforms.
+fn to_config_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn toml_array<'a>(values: impl IntoIterator<Item = &'a String>) -> String {
+    let values: Vec<String> = values
+        .into_iter()
+        .map(|value| toml::Value::from(value.as_str()).to_string())
+        .collect();
+    format!("[{}]", values.join(", "))
+}
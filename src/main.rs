@@ -1,123 +1,270 @@
+mod budget;
+mod business_days;
+mod calendar;
 mod cli;
 mod config;
+mod dates;
+mod diff;
+mod explain;
+mod expr;
+mod filter;
+mod fix;
+mod fragment;
+mod git;
+mod hint;
+mod init;
 mod languages;
 mod output;
+mod rewrite;
+mod schema;
+mod stats;
 mod todo_analyzer;
+mod validate;
+mod walk;
 
+use budget::BudgetTracker;
+use chrono::{DateTime, Days, Local};
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command};
 use config::Config;
+use dates::{DatePrecision, Deadlines};
+use filter::WarningFilter;
+use fix::Fixer;
 use languages::get_language_configs;
-use output::{print_json, print_table};
+use output::{
+    group_warnings, print_budget_json, print_budget_table, print_json, print_stats, print_table,
+};
+use stats::Stats;
 use std::collections::BTreeMap;
-use todo_analyzer::{TodoAnalyzer, TodoWarning};
-use walkdir::WalkDir;
+use std::path::Path;
+use todo_analyzer::{FileReport, TodoAnalyzer, TodoItem};
+use walk::{walk_rules, SourceFile, Tree};
 
-fn main() -> anyhow::Result<()> {
+/// Exit code when warnings at or above the `--fail-on` severity were found.
+const EXIT_VIOLATIONS: i32 = 1;
+/// Exit code when the tool itself failed, e.g. because of an invalid config file.
+const EXIT_ERROR: i32 = 2;
+
+fn main() {
     let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(true) => std::process::exit(EXIT_VIOLATIONS),
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Runs the requested command and returns whether it found violations.
+fn run(cli: &Cli) -> anyhow::Result<bool> {
+    if let Some(Command::Diff(args)) = &cli.command {
+        args.validate().map_err(|e| anyhow::anyhow!(e))?;
+        diff::run(args)?;
+        return Ok(false);
+    }
+    if let Some(Command::ValidateConfig(args)) = &cli.command {
+        return validate::run(args);
+    }
+    if let Some(Command::Init(args)) = &cli.command {
+        init::run(args)?;
+        return Ok(false);
+    }
+    if let Some(Command::Reassign(args)) = &cli.command {
+        args.validate().map_err(|e| anyhow::anyhow!(e))?;
+        rewrite::reassign(args)?;
+        return Ok(false);
+    }
+    if let Some(Command::Postpone(args)) = &cli.command {
+        args.validate().map_err(|e| anyhow::anyhow!(e))?;
+        rewrite::postpone(args)?;
+        return Ok(false);
+    }
+
+    if let Some(Command::Explain(args)) = &cli.command {
+        return explain::run(args);
+    }
+
     cli.validate().map_err(|e| anyhow::anyhow!(e))?;
 
-    let config = Config::from_file(&cli.config_file_path)?;
+    let config_file_path = Config::locate(cli.config_file_path.as_deref())?;
+    let config = Config::from_file(&config_file_path).map_err(|e| {
+        anyhow::anyhow!(
+            "{}\nRun `todo-reminder validate-config` to check the config file",
+            e
+        )
+    })?;
 
     let language_configs = get_language_configs();
     let mut analyzer = TodoAnalyzer::new(&language_configs)?;
-    let mut warnings_by_file: BTreeMap<String, Vec<TodoWarning>> = BTreeMap::new();
-    let config_file_dir = std::path::Path::new(&cli.config_file_path)
-        .parent()
-        .unwrap();
-    let current_dir = std::env::current_dir()?;
-    let abs_config_file_dir = if !config_file_dir.is_absolute() {
-        current_dir.join(&config_file_dir)
-    } else {
-        config_file_dir.to_path_buf().clone()
-    };
+    let mut reports_by_file: BTreeMap<String, FileReport> = BTreeMap::new();
+    let now = cli.now(&config.deadlines);
+    analyzer.set_deadlines(config.deadlines);
+    let mut budget_tracker = BudgetTracker::default();
+    let mut stats = Stats::default();
+    let abs_config_file_dir = Config::base_dir(&config_file_path)?;
+    let filter = WarningFilter::new(cli, &config, &abs_config_file_dir)?;
+    let mut fixer = cli
+        .fix
+        .then(|| Fixer::new(cli, now.date_naive()))
+        .transpose()?;
 
-    for rule in &config.rules {
-        analyzer.set_language(&rule.language)?;
+    walk_rules(
+        &config,
+        &config_file_path,
+        &Tree::WorkingTree,
+        &mut analyzer,
+        |analyzer, file| {
+            let SourceFile {
+                path,
+                relative_path,
+                rule_index,
+                rule,
+                content,
+                ..
+            } = file;
+            let settings = file.settings.clone().with_cli(cli);
 
-        let config = match language_configs.get(&rule.language) {
-            Some(config) => config,
-            None => continue,
-        };
+            let mut todos = match analyzer.analyze_source(&content, &rule.language) {
+                Ok(todos) => todos,
+                Err(e) => {
+                    eprintln!("Error analyzing file {}: {}", path.display(), e);
+                    return Ok(());
+                }
+            };
 
-        let file_extensions = rule.file_extensions.clone().unwrap_or_else(|| {
-            config
-                .file_extensions
-                .iter()
-                .map(|s| s.to_string())
-                .collect()
-        });
-
-        for rule_path in &rule.paths {
-            let abs_path = abs_config_file_dir.join(rule_path);
-
-            if !abs_path.exists() || !abs_path.is_dir() {
-                eprintln!(
-                    "Directory does not exist or not a folder: {}",
-                    abs_path.display()
-                );
-                continue;
+            if let Some(fixer) = &mut fixer {
+                if filter.matches_path(&relative_path) {
+                    fixer.fix_file(analyzer, &config, path, &relative_path, &content, &todos)?;
+                }
+                return Ok(());
             }
 
-            for entry in WalkDir::new(&abs_path)
-                .into_iter()
-                .filter_map(Result::ok)
-                .filter(|e| e.file_type().is_file())
-            {
-                let path = entry.path();
-                let extension = path.extension().and_then(|ext| ext.to_str());
-
-                if extension.is_none() {
-                    continue;
-                }
-                let ext = extension.unwrap();
+            let parameters = &config.parameters;
+            if config.needs_git_history() && !todos.is_empty() {
+                set_introduced_at(&mut todos, path);
+            }
+            if let Some(implicit_due_days) = parameters.implicit_due_days {
+                set_implicit_due_dates(&mut todos, path, implicit_due_days, &config.deadlines);
+            }
 
-                if !file_extensions.contains(&ext.to_string()) {
-                    continue;
-                }
+            let mut warnings = analyzer.check_todos(&todos, &settings, now, &config);
+            // sort warnings by line number
+            warnings.sort_by_key(|w| w.line_number());
 
-                match analyzer.analyze_file(path, &rule.language) {
-                    Ok(todos) => {
-                        let mut warnings = analyzer.check_todos(&todos, &cli);
-                        // sort warnings by line number
-                        warnings.sort_by_key(|w| w.line_number());
-
-                        let relative_path = path
-                            .strip_prefix(&abs_config_file_dir)?
-                            .display()
-                            .to_string();
-                        warnings_by_file
-                            .entry(relative_path)
-                            .or_insert_with(Vec::new)
-                            .extend(warnings);
-                    }
-                    Err(e) => {
-                        eprintln!("Error analyzing file {}: {}", path.display(), e);
-                    }
-                }
+            let in_path = filter.matches_path(&relative_path);
+            warnings.retain(|warning| {
+                warning.is_checked(&settings)
+                    && in_path
+                    && filter.matches(&relative_path, &settings, warning)
+            });
+            // Budgets and stats cover the same TODOs as the output, so with an owner, type
+            // or expression filter only the TODOs of the reported warnings count
+            if filter.filters_warnings() {
+                todos.retain(|todo| {
+                    warnings
+                        .iter()
+                        .any(|warning| warning.line_number() == todo.line_number)
+                });
             }
-        }
+            if in_path {
+                budget_tracker.record(&config, rule_index, &relative_path, &todos, &warnings);
+                stats.record(
+                    rule_index,
+                    &rule.language,
+                    &relative_path,
+                    content.lines().count(),
+                    &todos,
+                    &warnings,
+                );
+            }
+            reports_by_file
+                .entry(relative_path)
+                .or_insert_with(|| FileReport {
+                    warnings: Vec::new(),
+                    settings: settings.clone(),
+                })
+                .warnings
+                .extend(warnings);
+            Ok(())
+        },
+    )?;
+
+    if let Some(fixer) = &mut fixer {
+        fixer.print_summary();
+        return Ok(false);
     }
 
+    let budget_usages = config.has_budgets().then(|| budget_tracker.report(&config));
+
+    let groups = group_warnings(&reports_by_file, &cli.group_by, &cli.sort_by);
     match cli.format.as_str() {
-        "json" => print_json(&warnings_by_file),
-        _ => print_table(
-            &warnings_by_file,
-            &cli,
-            config.parameters.editor_url.as_deref(),
-        ),
+        "json" => print_json(&groups, &config.severity, &cli.display_date_format),
+        "budgets" => print_budget_json(budget_usages.as_deref().unwrap_or_default()),
+        "stats" => print_stats(&stats, &config, cli),
+        _ => {
+            print_table(&groups, cli, &config);
+            if let Some(budget_usages) = &budget_usages {
+                print_budget_table(budget_usages, cli);
+            }
+        }
     }
 
-    // Count actual warnings across all files
-    let mut total_warnings = 0;
-    for (_, warnings) in &warnings_by_file {
-        total_warnings += warnings.len();
-    }
+    // With budgets, only fail if one of them is exceeded. Otherwise fail if there are
+    // warnings at or above the --fail-on severity.
+    let has_violations = match (cli.fail_on_severity(), &budget_usages) {
+        (None, _) => false,
+        (Some(_), Some(budget_usages)) => budget_usages.iter().any(|usage| usage.is_exceeded()),
+        (Some(fail_on), None) => reports_by_file
+            .values()
+            .flat_map(|report| &report.warnings)
+            .any(|warning| warning.severity(&config.severity) >= fail_on),
+    };
+
+    Ok(has_violations)
+}
+
+/// Looks up when each TODO line was last committed in git, leaving the age unknown for
+/// files outside of a repository and lines that aren't committed yet.
+fn set_introduced_at(todos: &mut [TodoItem], path: &Path) {
+    let lines = match git::blame_lines(path) {
+        Ok(lines) => lines,
+        Err(_) => return,
+    };
 
-    // Only exit with code 1 if there are actual warnings and exit_zero is not set
-    if !cli.exit_zero && total_warnings > 0 {
-        std::process::exit(1);
+    for todo in todos {
+        todo.introduced_at = lines
+            .get(todo.line_number - 1)
+            .filter(|line| line.is_committed())
+            .map(|line| line.time);
     }
+}
+
+/// Gives TODOs without a due date one that is `implicit_due_days` after the TODO line was
+/// last changed, falling back to the modification time of the file outside of git.
+fn set_implicit_due_dates(
+    todos: &mut [TodoItem],
+    path: &Path,
+    implicit_due_days: u32,
+    deadlines: &Deadlines,
+) {
+    let modified_at = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified_at| DateTime::<Local>::from(modified_at).fixed_offset())
+        .ok();
 
-    Ok(())
+    for todo in todos
+        .iter_mut()
+        .filter(|todo| todo.due_date.is_none() && todo.due_name.is_none())
+    {
+        let base = match todo.introduced_at.or(modified_at) {
+            Some(base) => base,
+            None => continue,
+        };
+        let due_date = base.date_naive() + Days::new(implicit_due_days as u64);
+        todo.due_date = deadlines.due(due_date.into(), DatePrecision::Day);
+        todo.implicit_due_date = todo.due_date.is_some();
+    }
 }
@@ -0,0 +1,94 @@
+use crate::todo_analyzer::{FileReport, TodoWarning};
+use std::collections::BTreeMap;
+
+/// A warning along with the file it was found in.
+pub struct Entry<'a> {
+    pub file_path: &'a str,
+    pub report: &'a FileReport,
+    pub warning: &'a TodoWarning,
+}
+
+/// The warnings printed together, e.g. in one table.
+pub struct WarningGroup<'a> {
+    /// The file, owner or type of the warnings, or `None` with `--group-by=none`.
+    pub label: Option<String>,
+    pub entries: Vec<Entry<'a>>,
+}
+
+/// Splits the warnings into groups as given by `--group-by`, each sorted by `--sort-by`.
+/// Groups are ordered by file path, by owner with unowned TODOs last, or by the urgency of
+/// the type.
+pub fn group_warnings<'a>(
+    reports_by_file: &'a BTreeMap<String, FileReport>,
+    group_by: &str,
+    sort_by: &str,
+) -> Vec<WarningGroup<'a>> {
+    let mut entries: Vec<Entry> = reports_by_file
+        .iter()
+        .flat_map(|(file_path, report)| {
+            report.warnings.iter().map(move |warning| Entry {
+                file_path,
+                report,
+                warning,
+            })
+        })
+        .collect();
+
+    // Sorts are stable, so ties keep the order of file path and line
+    entries.sort_by_key(|entry| (entry.file_path, entry.warning.line_number()));
+    match sort_by {
+        "due" => entries.sort_by_key(|entry| {
+            let due_date = entry.warning.due_date();
+            (due_date.is_none(), due_date)
+        }),
+        "owner" => entries.sort_by_key(|entry| owner_key(entry.warning)),
+        "type" => entries.sort_by_key(|entry| type_rank(entry.warning)),
+        _ => {}
+    }
+
+    let mut groups: Vec<WarningGroup> = Vec::new();
+    for entry in entries {
+        let label = match group_by {
+            "file" => Some(entry.file_path.to_string()),
+            "owner" => Some(match entry.warning.owner() {
+                Some(owner) => format!("@{}", owner),
+                None => "(no owner)".to_string(),
+            }),
+            "type" => Some(entry.warning.type_name().to_string()),
+            _ => None,
+        };
+        match groups.iter_mut().find(|group| group.label == label) {
+            Some(group) => group.entries.push(entry),
+            None => groups.push(WarningGroup {
+                label,
+                entries: vec![entry],
+            }),
+        }
+    }
+
+    let first = |group: &WarningGroup<'a>| group.entries[0].warning;
+    match group_by {
+        "file" => groups.sort_by(|a, b| a.label.cmp(&b.label)),
+        "owner" => groups.sort_by_key(|group| owner_key(first(group))),
+        "type" => groups.sort_by_key(|group| type_rank(first(group))),
+        _ => {}
+    }
+    groups
+}
+
+/// Sorts by owner, with unowned TODOs last.
+fn owner_key(warning: &TodoWarning) -> (bool, Option<&str>) {
+    (warning.owner().is_none(), warning.owner())
+}
+
+/// Sorts the most urgent warnings first.
+fn type_rank(warning: &TodoWarning) -> usize {
+    match warning {
+        TodoWarning::Overdue { .. } => 0,
+        TodoWarning::DueSoon { .. } => 1,
+        TodoWarning::Stale { .. } => 2,
+        TodoWarning::PolicyViolation { .. } => 3,
+        TodoWarning::UnknownDueDate { .. } => 4,
+        TodoWarning::InvalidFormat { .. } => 5,
+    }
+}
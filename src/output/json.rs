@@ -1,54 +1,193 @@
-use crate::todo_analyzer::TodoWarning;
+use crate::budget::BudgetUsage;
+use crate::config::Severities;
+use crate::diff::{TodoChangeKind, TodoDiff};
+use crate::output::grouping::WarningGroup;
+use crate::todo_analyzer::{TodoItem, TodoWarning};
 use serde_json::json;
-use std::collections::BTreeMap;
 
-pub fn print_json(warnings_by_file: &BTreeMap<String, Vec<TodoWarning>>) {
+/// Prints the warnings as a JSON array in the order of the groups. Due dates of a single day
+/// are written in `date_format`.
+pub fn print_json(groups: &[WarningGroup], severities: &Severities, date_format: &str) {
     let mut json_warnings = Vec::new();
 
-    for (file_path, warnings) in warnings_by_file {
-        for warning in warnings {
-            let warning_json = match warning {
+    for group in groups {
+        for entry in &group.entries {
+            let (file_path, report, warning) = (entry.file_path, entry.report, entry.warning);
+            let mut warning_json = match warning {
                 TodoWarning::InvalidFormat {
                     line_number,
                     comment,
+                    hint,
+                    ..
+                } => {
+                    let mut warning_json = json!({
+                        "file": file_path,
+                        "line": line_number,
+                        "type": "InvalidFormat",
+                        "comment": comment,
+                    });
+                    if let Some(hint) = hint {
+                        warning_json["hint"] = json!(hint.to_string());
+                    }
+                    warning_json
+                }
+                TodoWarning::Stale {
+                    line_number,
+                    comment,
+                    ..
                 } => json!({
                     "file": file_path,
                     "line": line_number,
-                    "type": "InvalidFormat",
+                    "type": "Stale",
                     "comment": comment,
                 }),
                 TodoWarning::Overdue {
                     line_number,
-                    due_date,
                     owner,
                     comment,
+                    days_overdue,
+                    ..
                 } => json!({
                     "file": file_path,
                     "line": line_number,
                     "type": "Overdue",
-                    "due_date": due_date.format("%Y-%m-%d").to_string(),
+                    "due_date": warning.due_date_label(date_format),
                     "owner": owner,
                     "comment": comment,
+                    "days_overdue": days_overdue,
                 }),
                 TodoWarning::DueSoon {
                     line_number,
-                    due_date,
                     owner,
                     comment,
                     days_until_due,
+                    business_days_until_due,
+                    ..
+                } => {
+                    let mut warning_json = json!({
+                        "file": file_path,
+                        "line": line_number,
+                        "type": "DueSoon",
+                        "due_date": warning.due_date_label(date_format),
+                        "owner": owner,
+                        "comment": comment,
+                        "days_until_due": days_until_due,
+                    });
+                    if let Some(business_days_until_due) = business_days_until_due {
+                        warning_json["business_days_until_due"] = json!(business_days_until_due);
+                    }
+                    warning_json
+                }
+                TodoWarning::PolicyViolation {
+                    line_number,
+                    owner,
+                    comment,
+                    policy,
+                    message,
+                    ..
+                } => json!({
+                    "file": file_path,
+                    "line": line_number,
+                    "type": "PolicyViolation",
+                    "due_date": warning.due_date_label(date_format),
+                    "owner": owner,
+                    "comment": comment,
+                    "policy": policy,
+                    "message": message,
+                }),
+                TodoWarning::UnknownDueDate {
+                    line_number,
+                    due_name,
+                    owner,
+                    comment,
+                    message,
+                    ..
                 } => json!({
                     "file": file_path,
                     "line": line_number,
-                    "type": "DueSoon",
-                    "due_date": due_date.format("%Y-%m-%d").to_string(),
+                    "type": "UnknownDueDate",
+                    "due_name": due_name,
                     "owner": owner,
                     "comment": comment,
-                    "days_until_due": days_until_due,
+                    "message": message,
                 }),
             };
+            if let Some(due_name) = warning.due_name() {
+                warning_json["due_name"] = json!(due_name);
+            }
+            warning_json["severity"] = json!(warning.severity(severities).as_str());
+            if warning.has_implicit_due_date() {
+                warning_json["implicit_due_date"] = json!(true);
+            }
+            if let Some(age_days) = warning.age_days() {
+                warning_json["age_days"] = json!(age_days);
+            }
+            if let Some(team) = &report.settings.team {
+                warning_json["team"] = json!(team);
+            }
+            if !report.settings.labels.is_empty() {
+                warning_json["labels"] = json!(report.settings.labels);
+            }
             json_warnings.push(warning_json);
         }
     }
 
     println!("{}", serde_json::to_string_pretty(&json_warnings).unwrap());
 }
+
+/// Prints the usage of each budget as a JSON array.
+pub fn print_budget_json(budget_usages: &[BudgetUsage]) {
+    let budgets: Vec<_> = budget_usages
+        .iter()
+        .map(|usage| {
+            json!({
+                "scope": usage.scope,
+                "metric": usage.metric,
+                "limit": usage.limit,
+                "used": usage.used,
+                "exceeded": usage.is_exceeded(),
+            })
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&budgets).unwrap());
+}
+
+pub fn print_diff_json(diff: &TodoDiff, date_format: &str) {
+    let todo_json = |todo: &TodoItem| {
+        json!({
+            "line": todo.line_number,
+            "due_date": todo.due_date_label(date_format),
+            "owner": todo.owner,
+            "comment": todo.text,
+        })
+    };
+
+    let changes: Vec<_> = diff
+        .changes
+        .iter()
+        .map(|change| {
+            json!({
+                "file": change.file,
+                "type": format!("{:?}", change.kind),
+                "postponed": change.is_postponed(),
+                "reassigned": change.is_reassigned(),
+                "old": change.old.as_ref().map(todo_json),
+                "new": change.new.as_ref().map(todo_json),
+            })
+        })
+        .collect();
+
+    let output = json!({
+        "summary": {
+            "added": diff.count(TodoChangeKind::Added),
+            "resolved": diff.count(TodoChangeKind::Resolved),
+            "modified": diff.count(TodoChangeKind::Modified),
+            "postponed": diff.postponed(),
+            "reassigned": diff.reassigned(),
+        },
+        "changes": changes,
+    });
+
+    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+}
@@ -1,5 +1,9 @@
+mod grouping;
 mod json;
+mod stats;
 mod table;
 
-pub use json::print_json;
-pub use table::print_table;
+pub use grouping::group_warnings;
+pub use json::{print_budget_json, print_diff_json, print_json};
+pub use stats::print_stats;
+pub use table::{print_budget_table, print_diff_table, print_table};
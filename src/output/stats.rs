@@ -0,0 +1,129 @@
+use crate::budget::rule_scope;
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::stats::{Density, Stats};
+use comfy_table::{
+    modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, Attribute, Cell, CellAlignment, Table,
+};
+
+/// The width of the longest bar in the due date histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+
+pub fn print_stats(stats: &Stats, config: &Config, cli: &Cli) {
+    println!(
+        "{} TODOs in {} files with {} lines, {:.1} per 1000 lines",
+        stats.total.todos,
+        stats.total.files,
+        stats.total.lines,
+        stats.total.per_thousand_lines()
+    );
+
+    let mut by_status: Vec<(String, usize)> = stats
+        .by_status
+        .iter()
+        .map(|(status, count)| (status.to_string(), *count))
+        .collect();
+    print_counts("Status", &mut by_status, cli);
+
+    let mut by_owner: Vec<(String, usize)> = stats
+        .by_owner
+        .iter()
+        .map(|(owner, count)| match owner {
+            Some(owner) => (format!("@{}", owner), *count),
+            None => ("(none)".to_string(), *count),
+        })
+        .collect();
+    print_counts("Owner", &mut by_owner, cli);
+
+    print_densities(
+        "Language",
+        stats
+            .by_language
+            .iter()
+            .map(|(language, density)| (language.clone(), density)),
+        cli,
+    );
+    print_densities(
+        "Rule",
+        stats.by_rule.iter().map(|(rule_index, density)| {
+            (rule_scope(*rule_index, &config.rules[*rule_index]), density)
+        }),
+        cli,
+    );
+    print_densities(
+        "Directory",
+        stats
+            .by_directory
+            .iter()
+            .map(|(directory, density)| (directory.clone(), density)),
+        cli,
+    );
+
+    if !stats.by_due_month.is_empty() {
+        let max = stats.by_due_month.values().copied().max().unwrap_or(1);
+        let mut table = new_table(["Due Month", "TODOs", ""], cli);
+        for (month, count) in &stats.by_due_month {
+            let width = (count * HISTOGRAM_WIDTH).div_ceil(max);
+            table.add_row(vec![
+                Cell::new(month),
+                Cell::new(count).set_alignment(CellAlignment::Right),
+                Cell::new("█".repeat(width)).fg(comfy_table::Color::Cyan),
+            ]);
+        }
+        println!("{}", table);
+    }
+}
+
+/// Prints a table of counts, with the largest first.
+fn print_counts(name: &str, counts: &mut [(String, usize)], cli: &Cli) {
+    if counts.is_empty() {
+        return;
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut table = new_table([name, "TODOs"], cli);
+    for (label, count) in counts.iter() {
+        table.add_row(vec![
+            Cell::new(label),
+            Cell::new(count).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{}", table);
+}
+
+fn print_densities<'a>(
+    name: &str,
+    densities: impl Iterator<Item = (String, &'a Density)>,
+    cli: &Cli,
+) {
+    let mut table = new_table([name, "Files", "Lines", "TODOs", "Per 1000 Lines"], cli);
+    for (label, density) in densities {
+        table.add_row(vec![
+            Cell::new(label),
+            Cell::new(density.files).set_alignment(CellAlignment::Right),
+            Cell::new(density.lines).set_alignment(CellAlignment::Right),
+            Cell::new(density.todos).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.1}", density.per_thousand_lines()))
+                .set_alignment(CellAlignment::Right),
+        ]);
+    }
+    if !table.is_empty() {
+        println!("{}", table);
+    }
+}
+
+fn new_table<const N: usize>(headers: [&str; N], cli: &Cli) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS);
+    if cli.no_tty {
+        table.force_no_tty();
+    }
+    table.set_header(headers.into_iter().map(|header| {
+        Cell::new(header)
+            .fg(comfy_table::Color::Green)
+            .add_attribute(Attribute::Bold)
+    }));
+    table
+}
@@ -1,7 +1,11 @@
-use crate::cli::Cli;
-use crate::todo_analyzer::TodoWarning;
+use crate::budget::BudgetUsage;
+use crate::cli::{Cli, DiffArgs};
+use crate::config::{Config, Severity};
+use crate::diff::{TodoChangeKind, TodoDiff};
+use crate::hint::FormatHint;
+use crate::output::grouping::WarningGroup;
+use crate::todo_analyzer::{TodoItem, TodoWarning};
 use comfy_table::{modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, Attribute, Cell, Table};
-use std::collections::BTreeMap;
 use std::env;
 use std::path::Path;
 
@@ -10,68 +14,63 @@ fn format_multiline_comment(comment: &str) -> String {
     lines.join("\n")
 }
 
-pub fn print_table(
-    warnings_by_file: &BTreeMap<String, Vec<TodoWarning>>,
-    cli: &Cli,
-    editor_url: Option<&str>,
-) {
-    for (file_path, warnings) in warnings_by_file {
-        print_formatted_warnings(file_path, warnings, cli, editor_url);
-    }
-}
+pub fn print_table(groups: &[WarningGroup], cli: &Cli, config: &Config) {
+    let show_age = groups
+        .iter()
+        .flat_map(|group| &group.entries)
+        .any(|entry| entry.warning.age_days().is_some());
 
-fn print_formatted_warnings(
-    file_path: &str,
-    warnings: &[TodoWarning],
-    cli: &Cli,
-    editor_url: Option<&str>,
-) {
-    if warnings.is_empty() {
-        return;
+    for group in groups {
+        print_formatted_warnings(group, cli, config, show_age);
     }
+}
 
-    let has_warnings_to_display = warnings.iter().any(|warning| match warning {
-        TodoWarning::InvalidFormat { .. } => {
-            cli.check_format_only || (!cli.check_due_only && !cli.check_format_only)
-        }
-        TodoWarning::Overdue { .. } => {
-            cli.check_due_only || (!cli.check_due_only && !cli.check_format_only)
-        }
-        TodoWarning::DueSoon { .. } => {
-            cli.check_due_only || (!cli.check_due_only && !cli.check_format_only)
-        }
-    });
-
-    if !has_warnings_to_display {
-        return;
-    }
+fn print_formatted_warnings(group: &WarningGroup, cli: &Cli, config: &Config, show_age: bool) {
+    let editor_url = config.parameters.editor_url.as_deref();
+    let by_file = cli.group_by == "file";
+    let first_entry = match group.entries.first() {
+        Some(first_entry) => first_entry,
+        None => return,
+    };
 
-    let first_warning_line = warnings[0].line_number();
+    let title = if by_file {
+        let file_path = first_entry.file_path;
+        let settings = &first_entry.report.settings;
 
-    // Get the absolute path for the clickable link
-    let absolute_path = if Path::new(file_path).is_relative() {
-        if let Ok(current_dir) = env::current_dir() {
-            let absolute = current_dir.join(file_path);
-            match absolute.to_str() {
-                Some(abs_path) => abs_path.to_string(),
-                None => file_path.to_string(),
+        // Get the absolute path for the clickable link
+        let absolute_path = if Path::new(file_path).is_relative() {
+            if let Ok(current_dir) = env::current_dir() {
+                let absolute = current_dir.join(file_path);
+                match absolute.to_str() {
+                    Some(abs_path) => abs_path.to_string(),
+                    None => file_path.to_string(),
+                }
+            } else {
+                file_path.to_string()
             }
         } else {
             file_path.to_string()
-        }
-    } else {
-        file_path.to_string()
-    };
+        };
 
-    let clickable_file_link = if cli.no_tty {
-        file_path.to_string()
+        let mut clickable_file_link = if cli.no_tty {
+            file_path.to_string()
+        } else {
+            get_clickable_file_link(
+                &absolute_path,
+                first_entry.warning.line_number(),
+                &truncate_file_path(file_path, settings.max_comment_length),
+                editor_url,
+            )
+        };
+        if let Some(team) = &settings.team {
+            clickable_file_link.push_str(&format!(" [team: {}]", team));
+        }
+        if !settings.labels.is_empty() {
+            clickable_file_link.push_str(&format!(" [labels: {}]", settings.labels.join(", ")));
+        }
+        clickable_file_link
     } else {
-        get_clickable_file_link(
-            &absolute_path,
-            first_warning_line,
-            &truncate_file_path(file_path, cli.max_comment_length),
-            editor_url,
-        )
+        group.label.clone().unwrap_or_else(|| "TODOs".to_string())
     };
 
     let mut table = Table::new();
@@ -81,122 +80,245 @@ fn print_formatted_warnings(
     if cli.no_tty {
         table.force_no_tty();
     }
-    table.set_header(vec![
-        Cell::new("Line")
+
+    let mut header = vec![
+        Cell::new(if by_file { "Line" } else { "Location" })
             .fg(comfy_table::Color::Green)
             .add_attribute(Attribute::Bold),
         Cell::new("Type")
             .fg(comfy_table::Color::Green)
             .add_attribute(Attribute::Bold),
+        Cell::new("Severity")
+            .fg(comfy_table::Color::Green)
+            .add_attribute(Attribute::Bold),
         Cell::new("Due Date")
             .fg(comfy_table::Color::Green)
             .add_attribute(Attribute::Bold),
         Cell::new("Owner")
             .fg(comfy_table::Color::Green)
             .add_attribute(Attribute::Bold),
-        Cell::new(&clickable_file_link)
+    ];
+    if show_age {
+        header.push(
+            Cell::new("Age")
+                .fg(comfy_table::Color::Green)
+                .add_attribute(Attribute::Bold),
+        );
+    }
+    header.push(
+        Cell::new(&title)
             .fg(comfy_table::Color::Cyan)
             .add_attribute(Attribute::Bold),
-    ]);
+    );
+    table.set_header(header);
 
-    for warning in warnings {
-        match warning {
-            TodoWarning::InvalidFormat {
-                line_number,
-                comment,
-                ..
-            } => {
-                if cli.check_format_only || (!cli.check_due_only && !cli.check_format_only) {
-                    table.add_row(vec![
-                        Cell::new(if cli.no_tty {
-                            line_number.to_string()
-                        } else {
-                            get_clickable_file_link(
-                                file_path,
-                                *line_number,
-                                &line_number.to_string(),
-                                editor_url,
-                            )
-                        })
-                        .fg(comfy_table::Color::Yellow),
-                        Cell::new("Format").fg(comfy_table::Color::Magenta),
-                        Cell::new(""),
-                        Cell::new(""),
-                        Cell::new(&truncate_comment(
-                            &format_multiline_comment(comment),
-                            cli.max_comment_length,
-                        )),
-                    ]);
-                }
-            }
+    for entry in &group.entries {
+        let (file_path, warning) = (entry.file_path, entry.warning);
+        let max_comment_length = entry.report.settings.max_comment_length;
+        let (type_cell, due_date_cell, owner, comment) = match warning {
+            TodoWarning::InvalidFormat { comment, hint, .. } => (
+                Cell::new("Format").fg(comfy_table::Color::Magenta),
+                Cell::new(""),
+                &None,
+                match hint {
+                    Some(hint) => format_hint(comment, hint),
+                    None => comment.clone(),
+                },
+            ),
+            TodoWarning::Stale { comment, .. } => (
+                Cell::new("Stale").fg(comfy_table::Color::Red),
+                Cell::new(""),
+                &None,
+                comment.clone(),
+            ),
             TodoWarning::Overdue {
-                line_number,
-                due_date,
                 owner,
                 comment,
+                days_overdue,
+                implicit_due_date,
                 ..
-            } => {
-                if cli.check_due_only || (!cli.check_due_only && !cli.check_format_only) {
-                    table.add_row(vec![
-                        Cell::new(if cli.no_tty {
-                            line_number.to_string()
-                        } else {
-                            get_clickable_file_link(
-                                file_path,
-                                *line_number,
-                                &line_number.to_string(),
-                                editor_url,
-                            )
-                        })
-                        .fg(comfy_table::Color::Yellow),
-                        Cell::new("Overdue").fg(comfy_table::Color::Red),
-                        Cell::new(&due_date.format("%Y-%m-%d").to_string())
-                            .fg(comfy_table::Color::Red),
-                        Cell::new(owner.as_deref().unwrap_or("")),
-                        Cell::new(&truncate_comment(
-                            &format_multiline_comment(comment),
-                            cli.max_comment_length,
-                        )),
-                    ]);
-                }
-            }
+            } => (
+                Cell::new("Overdue").fg(comfy_table::Color::Red),
+                Cell::new(format_due_date(
+                    &warning
+                        .due_date_label(&cli.display_date_format)
+                        .unwrap_or_default(),
+                    warning.due_name(),
+                    *implicit_due_date,
+                    Some(*days_overdue),
+                ))
+                .fg(comfy_table::Color::Red),
+                owner,
+                comment.clone(),
+            ),
             TodoWarning::DueSoon {
-                line_number,
-                due_date,
                 owner,
                 comment,
+                implicit_due_date,
                 ..
-            } => {
-                if cli.check_due_only || (!cli.check_due_only && !cli.check_format_only) {
-                    table.add_row(vec![
-                        Cell::new(if cli.no_tty {
-                            line_number.to_string()
-                        } else {
-                            get_clickable_file_link(
-                                file_path,
-                                *line_number,
-                                &line_number.to_string(),
-                                editor_url,
-                            )
-                        })
-                        .fg(comfy_table::Color::Yellow),
-                        Cell::new("Due Soon").fg(comfy_table::Color::Yellow),
-                        Cell::new(&due_date.format("%Y-%m-%d").to_string())
-                            .fg(comfy_table::Color::Yellow),
-                        Cell::new(owner.as_deref().unwrap_or("")),
-                        Cell::new(&truncate_comment(
-                            &format_multiline_comment(comment),
-                            cli.max_comment_length,
-                        )),
-                    ]);
-                }
-            }
+            } => (
+                Cell::new("Due Soon").fg(comfy_table::Color::Yellow),
+                Cell::new(format_due_date(
+                    &warning
+                        .due_date_label(&cli.display_date_format)
+                        .unwrap_or_default(),
+                    warning.due_name(),
+                    *implicit_due_date,
+                    None,
+                ))
+                .fg(comfy_table::Color::Yellow),
+                owner,
+                comment.clone(),
+            ),
+            TodoWarning::PolicyViolation {
+                owner,
+                comment,
+                policy,
+                message,
+                ..
+            } => (
+                Cell::new("Policy").fg(comfy_table::Color::Magenta),
+                Cell::new(
+                    warning
+                        .due_date_label(&cli.display_date_format)
+                        .map(|due_date| format_due_date(&due_date, warning.due_name(), false, None))
+                        .unwrap_or_default(),
+                ),
+                owner,
+                format!("{}\n[{}] {}", comment, policy, message),
+            ),
+            TodoWarning::UnknownDueDate {
+                due_name,
+                owner,
+                comment,
+                message,
+                ..
+            } => (
+                Cell::new("Unknown").fg(comfy_table::Color::Magenta),
+                Cell::new(due_name),
+                owner,
+                format!("{}\n{}", comment, message),
+            ),
+        };
+
+        let line_number = warning.line_number();
+        let location = if by_file {
+            line_number.to_string()
+        } else {
+            format!(
+                "{}:{}",
+                truncate_file_path(file_path, max_comment_length),
+                line_number
+            )
+        };
+        let mut row = vec![
+            Cell::new(if cli.no_tty {
+                location
+            } else {
+                get_clickable_file_link(file_path, line_number, &location, editor_url)
+            })
+            .fg(comfy_table::Color::Yellow),
+            type_cell,
+            severity_cell(warning.severity(&config.severity)),
+            due_date_cell,
+            Cell::new(owner.as_deref().unwrap_or("")),
+        ];
+        if show_age {
+            row.push(Cell::new(
+                warning
+                    .age_days()
+                    .map(|age_days| format!("{} days", age_days))
+                    .unwrap_or_default(),
+            ));
         }
+        row.push(Cell::new(truncate_comment(
+            &format_multiline_comment(&comment),
+            max_comment_length,
+        )));
+        table.add_row(row);
     }
 
     println!("{}", table);
 }
 
+pub fn print_budget_table(usages: &[BudgetUsage], cli: &Cli) {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS);
+    if cli.no_tty {
+        table.force_no_tty();
+    }
+    table.set_header(
+        ["Budget", "Limit", "Used", "Status"]
+            .into_iter()
+            .map(|header| {
+                Cell::new(header)
+                    .fg(comfy_table::Color::Green)
+                    .add_attribute(Attribute::Bold)
+            }),
+    );
+
+    for usage in usages {
+        let status = if usage.is_exceeded() {
+            Cell::new("Exceeded").fg(comfy_table::Color::Red)
+        } else {
+            Cell::new("OK").fg(comfy_table::Color::Green)
+        };
+        table.add_row(vec![
+            Cell::new(format!("{} {}", usage.scope, usage.metric)),
+            Cell::new(usage.limit),
+            Cell::new(usage.used),
+            status,
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+fn format_due_date(
+    due_date: &str,
+    due_name: Option<&str>,
+    implicit: bool,
+    days_overdue: Option<i64>,
+) -> String {
+    let mut notes = Vec::new();
+    if let Some(due_name) = due_name {
+        notes.push(due_name.to_string());
+    }
+    if implicit {
+        notes.push("implicit".to_string());
+    }
+    if let Some(days_overdue) = days_overdue {
+        notes.push(format!("overdue by {} days", days_overdue));
+    }
+
+    if notes.is_empty() {
+        due_date.to_string()
+    } else {
+        format!("{} ({})", due_date, notes.join(", "))
+    }
+}
+
+fn severity_cell(severity: Severity) -> Cell {
+    let color = match severity {
+        Severity::Info => comfy_table::Color::Blue,
+        Severity::Warning => comfy_table::Color::Yellow,
+        Severity::Error => comfy_table::Color::Red,
+    };
+    Cell::new(severity.as_str()).fg(color)
+}
+
+/// The comment followed by what's wrong with its format and the suggested comment.
+fn format_hint(comment: &str, hint: &FormatHint) -> String {
+    let mut lines = vec![comment.to_string()];
+    if !hint.problems.is_empty() {
+        lines.push(format!("Hint: {}", hint.problems.join(". ")));
+    }
+    lines.push(format!("Did you mean: {}", hint.suggestion));
+    lines.join("\n")
+}
+
 fn truncate_file_path(file_path: &str, max_length: usize) -> String {
     if file_path.len() > max_length {
         format!("...{}", &file_path[file_path.len() - max_length..])
@@ -235,3 +357,91 @@ fn get_clickable_file_link(
         None => display_text.to_string(),
     }
 }
+
+pub fn print_diff_table(diff: &TodoDiff, args: &DiffArgs, editor_url: Option<&str>) {
+    if !diff.changes.is_empty() {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS);
+        if args.no_tty {
+            table.force_no_tty();
+        }
+        table.set_header(
+            ["Change", "File", "Line", "Due Date", "Owner", "Comment"]
+                .into_iter()
+                .map(|header| {
+                    Cell::new(header)
+                        .fg(comfy_table::Color::Green)
+                        .add_attribute(Attribute::Bold)
+                }),
+        );
+
+        for change in &diff.changes {
+            let todo = change.todo();
+            let (label, color) = match change.kind {
+                TodoChangeKind::Added => ("Added", comfy_table::Color::Yellow),
+                TodoChangeKind::Resolved => ("Resolved", comfy_table::Color::Green),
+                TodoChangeKind::Modified if change.is_postponed() => {
+                    ("Postponed", comfy_table::Color::Red)
+                }
+                TodoChangeKind::Modified if change.is_reassigned() => {
+                    ("Reassigned", comfy_table::Color::Magenta)
+                }
+                TodoChangeKind::Modified => ("Modified", comfy_table::Color::Magenta),
+            };
+
+            let format_due_date = |todo: &TodoItem| {
+                todo.due_date_label(&args.display_date_format)
+                    .unwrap_or_default()
+            };
+            let format_owner = |todo: &TodoItem| todo.owner.clone().unwrap_or_default();
+            let (due_date, owner) = match (&change.old, &change.new) {
+                (Some(old), Some(new)) => (
+                    format_change(&format_due_date(old), &format_due_date(new)),
+                    format_change(&format_owner(old), &format_owner(new)),
+                ),
+                _ => (format_due_date(todo), format_owner(todo)),
+            };
+
+            // Resolved TODOs only exist in the old revision, so their line can't be opened
+            let line = if change.kind == TodoChangeKind::Resolved {
+                format!("{} (old)", todo.line_number)
+            } else if args.no_tty {
+                todo.line_number.to_string()
+            } else {
+                get_clickable_file_link(
+                    &change.file,
+                    todo.line_number,
+                    &todo.line_number.to_string(),
+                    editor_url,
+                )
+            };
+
+            table.add_row(vec![
+                Cell::new(label).fg(color),
+                Cell::new(truncate_file_path(&change.file, args.max_comment_length))
+                    .fg(comfy_table::Color::Cyan),
+                Cell::new(line).fg(comfy_table::Color::Yellow),
+                Cell::new(due_date),
+                Cell::new(owner),
+                Cell::new(truncate_comment(
+                    &format_multiline_comment(&todo.text),
+                    args.max_comment_length,
+                )),
+            ]);
+        }
+
+        println!("{}", table);
+    }
+
+    println!("{}", diff.summary());
+}
+
+fn format_change(old: &str, new: &str) -> String {
+    if old == new {
+        new.to_string()
+    } else {
+        format!("{} -> {}", old, new)
+    }
+}
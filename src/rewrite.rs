@@ -0,0 +1,251 @@
+use crate::cli::{parse_days, PostponeArgs, ReassignArgs, RewriteArgs, WARNING_TYPES};
+use crate::config::Config;
+use crate::dates::{split_time_of_day, DateFormats, DatePrecision};
+use crate::fix::apply_edits;
+use crate::languages::get_language_configs;
+use crate::todo_analyzer::{TodoAnalyzer, TodoItem};
+use crate::walk::{walk_rules, Tree};
+use anyhow::Result;
+use chrono::Days;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// The part of a TODO that a subcommand rewrites.
+#[derive(Clone, Copy)]
+enum Token {
+    DueDate,
+    Owner,
+}
+
+/// The edits of a file, applied once all files were analyzed.
+struct FileEdits {
+    path: PathBuf,
+    content: String,
+    edits: Vec<(Range<usize>, String)>,
+}
+
+/// A rewritten TODO, for the summary.
+struct Change {
+    file: String,
+    line: usize,
+    old: String,
+    new: String,
+}
+
+/// Changes the owner of the TODOs of `--from` to `--to`.
+pub fn reassign(args: &ReassignArgs) -> Result<()> {
+    let from = args.from.trim_start_matches('@');
+    let to = args.to.trim_start_matches('@');
+    if to.is_empty() || !to.chars().all(|c| c.is_alphanumeric() || "_.-".contains(c)) {
+        anyhow::bail!("Invalid owner \"{}\"", args.to);
+    }
+
+    let changes = rewrite_todos(&args.rewrite, Token::Owner, |todo, _, _| {
+        (todo.owner.as_deref() == Some(from)).then(|| to.to_string())
+    })?;
+    print_summary(&args.rewrite, &changes, "@", "reassign", "Reassigned");
+    Ok(())
+}
+
+/// Moves the due date of the TODOs by `--by` or to `--to`.
+pub fn postpone(args: &PostponeArgs) -> Result<()> {
+    let owners: Vec<&str> = args
+        .owner
+        .iter()
+        .map(|owner| owner.trim_start_matches('@'))
+        .collect();
+    let by = args.by.as_deref().and_then(parse_days);
+
+    let changes = rewrite_todos(&args.rewrite, Token::DueDate, |todo, old, date_formats| {
+        if !owners.is_empty()
+            && !owners
+                .iter()
+                .any(|owner| todo.owner.as_deref() == Some(owner))
+        {
+            return None;
+        }
+        let new = match (args.to, by) {
+            // Sprints and milestones are replaced by the date
+            (Some(to), _) => date_formats.write(to, Some(old)),
+            // Days keep their format, and weeks, months, quarters or years their precision
+            (None, Some(by)) => {
+                let due_date = todo.due_date?.naive_local();
+                let due_date = due_date.checked_add_days(Days::new(by))?;
+                match todo.due_precision {
+                    DatePrecision::Day | DatePrecision::Time => {
+                        date_formats.write(due_date.date(), Some(old))
+                    }
+                    precision => precision.format(due_date, "%Y-%m-%d"),
+                }
+            }
+            (None, None) => return None,
+        };
+        // Times of day are kept as well
+        Some(match split_time_of_day(old) {
+            (_, Some(time_of_day)) => format!("{}T{}", new, time_of_day),
+            (_, None) => new,
+        })
+    })?;
+    print_summary(&args.rewrite, &changes, "", "postpone", "Postponed");
+    Ok(())
+}
+
+/// Replaces the token of each TODO with a valid format for which `new_value` returns a
+/// value, if it passes the path and type filters. `new_value` gets the TODO, the current
+/// token and the date formats of the file. Returns the changes sorted by file and line.
+fn rewrite_todos(
+    args: &RewriteArgs,
+    token: Token,
+    new_value: impl Fn(&TodoItem, &str, &DateFormats) -> Option<String>,
+) -> Result<Vec<Change>> {
+    let config_file_path = Config::locate(args.config_file_path.as_deref())?;
+    let config = Config::from_file(&config_file_path)?;
+    let language_configs = get_language_configs();
+    let mut analyzer = TodoAnalyzer::new(&language_configs)?;
+    let now = args.now(&config.deadlines);
+    analyzer.set_deadlines(config.deadlines);
+    let path_prefixes: Vec<PathBuf> = args.path_prefix.iter().map(PathBuf::from).collect();
+    let types: Vec<&str> = WARNING_TYPES
+        .iter()
+        .filter(|(name, _)| args.types.iter().any(|t| t == name))
+        .map(|(_, type_name)| *type_name)
+        .collect();
+
+    let mut edits_by_file: BTreeMap<String, FileEdits> = BTreeMap::new();
+    let mut changes = Vec::new();
+
+    walk_rules(
+        &config,
+        &config_file_path,
+        &Tree::WorkingTree,
+        &mut analyzer,
+        |analyzer, file| {
+            if !path_prefixes.is_empty()
+                && !path_prefixes
+                    .iter()
+                    .any(|prefix| Path::new(&file.relative_path).starts_with(prefix))
+            {
+                return Ok(());
+            }
+            let todos = match analyzer.analyze_source(&file.content, &file.rule.language) {
+                Ok(todos) => todos,
+                Err(e) => {
+                    eprintln!("Error analyzing file {}: {}", file.path.display(), e);
+                    return Ok(());
+                }
+            };
+
+            let mut edits = Vec::new();
+            for todo in todos.iter().filter(|todo| todo.is_valid_format) {
+                if !types.is_empty() {
+                    let warnings = analyzer.check_todos(
+                        std::slice::from_ref(todo),
+                        file.settings,
+                        now,
+                        &config,
+                    );
+                    if !warnings
+                        .iter()
+                        .any(|warning| types.contains(&warning.type_name()))
+                    {
+                        continue;
+                    }
+                }
+
+                let comment = &file.content[todo.byte_range.clone()];
+                let range = match (analyzer.match_format(comment), token) {
+                    (Some(format_match), Token::DueDate) => match format_match.due_date {
+                        Some(range) => range,
+                        None => continue,
+                    },
+                    (Some(format_match), Token::Owner) => format_match.owner,
+                    (None, _) => continue,
+                };
+                let old = &comment[range.clone()];
+                let new = match new_value(todo, old, analyzer.date_formats()) {
+                    Some(new) if new != old => new,
+                    _ => continue,
+                };
+                let start = todo.byte_range.start;
+                let range = start + range.start..start + range.end;
+                changes.push(Change {
+                    file: file.relative_path.clone(),
+                    line: todo.line_number,
+                    old: old.to_string(),
+                    new: new.clone(),
+                });
+                edits.push((range, new));
+            }
+
+            if !edits.is_empty() {
+                edits_by_file.insert(
+                    file.relative_path,
+                    FileEdits {
+                        path: file.path.to_path_buf(),
+                        content: file.content,
+                        edits,
+                    },
+                );
+            }
+            Ok(())
+        },
+    )?;
+
+    for (relative_path, file) in edits_by_file {
+        apply_edits(
+            &file.path,
+            &relative_path,
+            &file.content,
+            file.edits,
+            args.dry_run,
+        )?;
+    }
+
+    changes.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    Ok(changes)
+}
+
+/// Lists each change and the totals, on stderr with `--dry-run` so that the diff can be
+/// piped into a file.
+fn print_summary(
+    args: &RewriteArgs,
+    changes: &[Change],
+    prefix: &str,
+    verb: &str,
+    past_tense: &str,
+) {
+    let mut lines: Vec<String> = changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{}:{}: {}{} -> {}{}",
+                change.file, change.line, prefix, change.old, prefix, change.new
+            )
+        })
+        .collect();
+
+    let todos = changes.len();
+    let mut files: Vec<&str> = changes.iter().map(|change| change.file.as_str()).collect();
+    files.dedup();
+    lines.push(format!(
+        "{} {} TODO{} in {} file{}",
+        if args.dry_run {
+            format!("Would {}", verb)
+        } else {
+            past_tense.to_string()
+        },
+        todos,
+        if todos == 1 { "" } else { "s" },
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    ));
+
+    for line in lines {
+        if args.dry_run {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
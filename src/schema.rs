@@ -0,0 +1,267 @@
+use crate::config::TodoFormat;
+use crate::languages::get_language_configs;
+use serde_json::{json, Map, Value};
+
+/// The JSON Schema of the config file, for editor autocompletion and `validate-config`.
+pub fn config_schema() -> Value {
+    let mut languages: Vec<String> = get_language_configs().into_keys().collect();
+    languages.sort();
+
+    let mut parameters = rule_settings();
+    parameters.extend(object(json!({
+        "editor_url": {
+            "type": "string",
+            "description": "The URL to open a file in the editor, with %%file%% and %%line%% placeholders"
+        },
+        "stale_after_days": {
+            "type": "integer",
+            "description": "Report TODOs without a due date that are older than this many days as stale"
+        },
+        "implicit_due_days": {
+            "type": "integer",
+            "description": "Give TODOs without a due date one that many days after they were last changed"
+        },
+        "calendar": {
+            "type": "string",
+            "description": "A TOML or CSV file with the end dates of sprints and milestones, relative to the config file"
+        },
+        "timezone": {
+            "type": "string",
+            "description": "The IANA timezone of due dates, e.g. Europe/Berlin, instead of the local one"
+        },
+        "due_at": {
+            "type": "string",
+            "enum": ["start_of_day", "end_of_day"],
+            "description": "Whether TODOs are overdue from the start or after the end of their due date"
+        }
+    })));
+
+    let mut rule = rule_settings();
+    rule.extend(object(json!({
+        "paths": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Directories to scan, relative to the config file"
+        },
+        "language": {
+            "type": "string",
+            "enum": languages,
+            "description": "The language of the files"
+        },
+        "file_extensions": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Extensions of the files to scan, without the leading dot"
+        },
+        "exclude": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Files and directories to skip, relative to the config file"
+        },
+        "budget": { "$ref": "#/definitions/budget" }
+    })));
+
+    let severity = json!({ "type": "string", "enum": ["info", "warning", "error"] });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "todo-reminder config",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "extends": {
+                "type": "string",
+                "description": "A config file to build on, relative to this file"
+            },
+            "include": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Config files to merge in, relative to this file"
+            },
+            "parameters": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": parameters
+            },
+            "rules": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["paths", "language"],
+                    "properties": rule
+                }
+            },
+            "policy": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "max_due_horizon_days": { "type": "integer" },
+                    "min_description_length": { "type": "integer" },
+                    "require_issue_reference": { "type": "boolean" },
+                    "issue_reference_pattern": { "type": "string" },
+                    "forbid_past_date_on_new_todos": { "type": "boolean" }
+                }
+            },
+            "severity": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "invalid_format": severity,
+                    "stale": severity,
+                    "due_soon": severity,
+                    "overdue": severity,
+                    "grace_period_days": { "type": "integer" },
+                    "grace_period": severity,
+                    "policy_violation": severity,
+                    "unknown_due_date": severity
+                }
+            },
+            "business_days": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "weekend": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "The days of the week that aren't business days [default: [\"sat\", \"sun\"]]"
+                    },
+                    "holidays": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Holidays as YYYY-MM-DD"
+                    },
+                    "holidays_file": {
+                        "type": "string",
+                        "description": "An .ics or CSV file with more holidays, relative to the config file"
+                    }
+                },
+                "description": "Count the due-soon window in business days, skipping weekends and holidays"
+            },
+            "owner_aliases": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                },
+                "description": "The git emails and names of each owner, for --mine"
+            },
+            "views": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["filter"],
+                    "properties": {
+                        "filter": {
+                            "type": "string",
+                            "description": "The filter expression of the view, selected with --view"
+                        }
+                    }
+                }
+            },
+            "budgets": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "directories": {
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/definitions/budget" }
+                    },
+                    "owners": {
+                        "type": "object",
+                        "additionalProperties": { "$ref": "#/definitions/budget" }
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "budget": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "max_todos": { "type": "integer" },
+                    "max_overdue": { "type": "integer" },
+                    "max_invalid": { "type": "integer" }
+                }
+            }
+        }
+    })
+}
+
+/// The JSON Schema of the `.todo-reminder.toml` fragments in subdirectories.
+pub fn fragment_schema() -> Value {
+    let mut properties = rule_settings();
+    properties.extend(object(json!({
+        "disable": {
+            "type": "boolean",
+            "description": "Skip this directory and its subdirectories"
+        },
+        "exclude": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Files and directories to skip, relative to the fragment"
+        }
+    })));
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "todo-reminder directory fragment",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties
+    })
+}
+
+/// The settings shared by `[parameters]`, rules and fragments.
+fn rule_settings() -> Map<String, Value> {
+    object(json!({
+        "due_in": {
+            "type": "integer",
+            "description": "Report TODOs that are due within this many days"
+        },
+        "check_format_only": {
+            "type": "boolean",
+            "description": "Only check the format of TODOs"
+        },
+        "check_due_only": {
+            "type": "boolean",
+            "description": "Only check whether TODOs are overdue or due soon"
+        },
+        "max_comment_length": {
+            "type": "integer",
+            "description": "Truncate comments to this length in the table output"
+        },
+        "team": {
+            "type": "string",
+            "description": "The team that owns the files"
+        },
+        "labels": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Labels of the files"
+        },
+        "keywords": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "The words that mark a comment as a TODO"
+        },
+        "formats": {
+            "type": "array",
+            "items": { "type": "string", "enum": TodoFormat::ALL.map(|format| format.as_str()) },
+            "description": "The conventions for the due date and owner of valid TODOs"
+        },
+        "date_formats": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "The chrono formats that due dates of a single day can be written in, e.g. %d.%m.%Y"
+        }
+    }))
+}
+
+fn object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,99 @@
+use crate::todo_analyzer::{TodoItem, TodoWarning};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// Status of TODOs without any warnings.
+pub const STATUS_OK: &str = "Ok";
+
+/// Files, lines and TODOs of a part of the codebase.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Density {
+    pub files: usize,
+    pub lines: usize,
+    pub todos: usize,
+}
+
+impl Density {
+    fn add(&mut self, lines: usize, todos: usize) {
+        self.files += 1;
+        self.lines += lines;
+        self.todos += todos;
+    }
+
+    pub fn per_thousand_lines(&self) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            self.todos as f64 * 1000.0 / self.lines as f64
+        }
+    }
+}
+
+/// Aggregate numbers about the TODOs of all scanned files, for `--format=stats`.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub total: Density,
+    /// TODOs by their first warning type, or `STATUS_OK`.
+    pub by_status: BTreeMap<&'static str, usize>,
+    /// TODOs by owner, with `None` for TODOs without one.
+    pub by_owner: BTreeMap<Option<String>, usize>,
+    pub by_language: BTreeMap<String, Density>,
+    /// Keyed by the index of the rule in the config.
+    pub by_rule: BTreeMap<usize, Density>,
+    pub by_directory: BTreeMap<String, Density>,
+    /// TODOs with a due date by month, as `YYYY-MM`.
+    pub by_due_month: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    pub fn record(
+        &mut self,
+        rule_index: usize,
+        language: &str,
+        file_path: &str,
+        lines: usize,
+        todos: &[TodoItem],
+        warnings: &[TodoWarning],
+    ) {
+        self.total.add(lines, todos.len());
+        self.by_language
+            .entry(language.to_string())
+            .or_default()
+            .add(lines, todos.len());
+        self.by_rule
+            .entry(rule_index)
+            .or_default()
+            .add(lines, todos.len());
+        self.by_directory
+            .entry(top_level_dir(file_path))
+            .or_default()
+            .add(lines, todos.len());
+
+        for todo in todos {
+            let status = warnings
+                .iter()
+                .find(|warning| warning.line_number() == todo.line_number)
+                .map_or(STATUS_OK, |warning| warning.type_name());
+            *self.by_status.entry(status).or_default() += 1;
+            *self.by_owner.entry(todo.owner.clone()).or_default() += 1;
+            if let Some(due_date) = todo.due_date {
+                *self
+                    .by_due_month
+                    .entry(due_date.format("%Y-%m").to_string())
+                    .or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Returns the first directory of a path relative to the config file, or `.` for files
+/// next to it.
+fn top_level_dir(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    match path.components().next() {
+        Some(Component::Normal(dir)) if path.components().count() > 1 => {
+            dir.to_string_lossy().to_string()
+        }
+        _ => ".".to_string(),
+    }
+}
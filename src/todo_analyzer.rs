@@ -1,20 +1,53 @@
-use crate::cli::Cli;
+use crate::calendar::{is_calendar_name, CALENDAR_NAME_PATTERN};
+use crate::config::{
+    Config, Policy, RuleSettings, Severities, Severity, TodoFormat, DEFAULT_DATE_FORMATS,
+    DEFAULT_FORMATS, DEFAULT_KEYWORDS,
+};
+use crate::dates::{DateError, DateFormats, DatePrecision, Deadlines};
+use crate::hint::{FormatHint, LooseTodo};
 use crate::languages::LanguageConfig;
+use crate::validate::{did_you_mean, suggest};
 use anyhow::Result;
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use chrono::{DateTime, FixedOffset};
 use regex::Regex;
 use std::collections::HashMap;
-use std::path::Path;
+use std::ops::Range;
 use streaming_iterator::StreamingIterator;
 use tree_sitter::{Parser, Query, QueryCursor};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TodoItem {
     pub text: String,
-    pub due_date: Option<DateTime<Local>>,
+    /// The last day of the period the TODO is due in. `diff` also sets it to the end date
+    /// of the sprint or milestone.
+    pub due_date: Option<DateTime<FixedOffset>>,
+    /// How precisely the due date was written.
+    pub due_precision: DatePrecision,
+    /// The sprint or milestone the TODO is due at, resolved with the calendar.
+    pub due_name: Option<String>,
     pub owner: Option<String>,
+    pub description: String,
     pub line_number: usize,
     pub is_valid_format: bool,
+    pub introduced_at: Option<DateTime<FixedOffset>>,
+    pub implicit_due_date: bool,
+    /// The byte range of the whole comment in the source.
+    pub byte_range: Range<usize>,
+    /// What's wrong with an invalid format, along with a suggested comment.
+    pub hint: Option<FormatHint>,
+}
+
+impl TodoItem {
+    /// The sprint or milestone, or the due date with the precision it was written with,
+    /// e.g. `2025-Q2`, and days in `day_format`.
+    pub fn due_date_label(&self, day_format: &str) -> Option<String> {
+        self.due_name.clone().or_else(|| {
+            self.due_date.map(|due_date| {
+                self.due_precision
+                    .format(due_date.naive_local(), day_format)
+            })
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -22,36 +55,225 @@ pub enum TodoWarning {
     InvalidFormat {
         line_number: usize,
         comment: String,
+        age_days: Option<i64>,
+        hint: Option<FormatHint>,
+    },
+    Stale {
+        line_number: usize,
+        comment: String,
+        age_days: i64,
     },
     Overdue {
         line_number: usize,
-        due_date: DateTime<Local>,
+        due_date: DateTime<FixedOffset>,
+        due_precision: DatePrecision,
+        due_name: Option<String>,
         owner: Option<String>,
         comment: String,
+        days_overdue: i64,
+        age_days: Option<i64>,
+        implicit_due_date: bool,
     },
     DueSoon {
         line_number: usize,
-        due_date: DateTime<Local>,
+        due_date: DateTime<FixedOffset>,
+        due_precision: DatePrecision,
+        due_name: Option<String>,
         owner: Option<String>,
         comment: String,
         days_until_due: i64,
+        /// Set with the `[business_days]` section, which counts the due-soon window.
+        business_days_until_due: Option<i64>,
+        age_days: Option<i64>,
+        implicit_due_date: bool,
+    },
+    PolicyViolation {
+        line_number: usize,
+        due_date: Option<DateTime<FixedOffset>>,
+        due_precision: DatePrecision,
+        due_name: Option<String>,
+        owner: Option<String>,
+        comment: String,
+        policy: &'static str,
+        message: String,
+        age_days: Option<i64>,
+    },
+    /// A sprint or milestone that isn't in the calendar.
+    UnknownDueDate {
+        line_number: usize,
+        due_name: String,
+        owner: Option<String>,
+        comment: String,
+        message: String,
+        age_days: Option<i64>,
     },
 }
 
 impl TodoWarning {
+    /// The name of the warning type, as used in the JSON output.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TodoWarning::InvalidFormat { .. } => "InvalidFormat",
+            TodoWarning::Stale { .. } => "Stale",
+            TodoWarning::Overdue { .. } => "Overdue",
+            TodoWarning::DueSoon { .. } => "DueSoon",
+            TodoWarning::PolicyViolation { .. } => "PolicyViolation",
+            TodoWarning::UnknownDueDate { .. } => "UnknownDueDate",
+        }
+    }
+
     pub fn line_number(&self) -> usize {
         match self {
             TodoWarning::InvalidFormat { line_number, .. } => *line_number,
+            TodoWarning::Stale { line_number, .. } => *line_number,
             TodoWarning::Overdue { line_number, .. } => *line_number,
             TodoWarning::DueSoon { line_number, .. } => *line_number,
+            TodoWarning::PolicyViolation { line_number, .. } => *line_number,
+            TodoWarning::UnknownDueDate { line_number, .. } => *line_number,
+        }
+    }
+
+    /// The due date of the TODO, if it has a valid one.
+    pub fn due_date(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            TodoWarning::InvalidFormat { .. }
+            | TodoWarning::Stale { .. }
+            | TodoWarning::UnknownDueDate { .. } => None,
+            TodoWarning::Overdue { due_date, .. } => Some(*due_date),
+            TodoWarning::DueSoon { due_date, .. } => Some(*due_date),
+            TodoWarning::PolicyViolation { due_date, .. } => *due_date,
+        }
+    }
+
+    /// The due date with the precision it was written with, e.g. `2025-Q2`, and days in
+    /// `day_format`.
+    pub fn due_date_label(&self, day_format: &str) -> Option<String> {
+        let due_precision = match self {
+            TodoWarning::InvalidFormat { .. }
+            | TodoWarning::Stale { .. }
+            | TodoWarning::UnknownDueDate { .. } => return None,
+            TodoWarning::Overdue { due_precision, .. }
+            | TodoWarning::DueSoon { due_precision, .. }
+            | TodoWarning::PolicyViolation { due_precision, .. } => *due_precision,
+        };
+        Some(due_precision.format(self.due_date()?.naive_local(), day_format))
+    }
+
+    /// The sprint or milestone the TODO is due at.
+    pub fn due_name(&self) -> Option<&str> {
+        match self {
+            TodoWarning::InvalidFormat { .. } | TodoWarning::Stale { .. } => None,
+            TodoWarning::Overdue { due_name, .. }
+            | TodoWarning::DueSoon { due_name, .. }
+            | TodoWarning::PolicyViolation { due_name, .. } => due_name.as_deref(),
+            TodoWarning::UnknownDueDate { due_name, .. } => Some(due_name),
+        }
+    }
+
+    pub fn comment(&self) -> &str {
+        match self {
+            TodoWarning::InvalidFormat { comment, .. }
+            | TodoWarning::Stale { comment, .. }
+            | TodoWarning::Overdue { comment, .. }
+            | TodoWarning::DueSoon { comment, .. }
+            | TodoWarning::PolicyViolation { comment, .. }
+            | TodoWarning::UnknownDueDate { comment, .. } => comment,
+        }
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            TodoWarning::InvalidFormat { .. } | TodoWarning::Stale { .. } => None,
+            TodoWarning::Overdue { owner, .. }
+            | TodoWarning::DueSoon { owner, .. }
+            | TodoWarning::PolicyViolation { owner, .. }
+            | TodoWarning::UnknownDueDate { owner, .. } => owner.as_deref(),
+        }
+    }
+
+    /// Whether the check modes of the rule report this kind of warning.
+    pub fn is_checked(&self, settings: &RuleSettings) -> bool {
+        match self {
+            TodoWarning::InvalidFormat { .. }
+            | TodoWarning::Stale { .. }
+            | TodoWarning::PolicyViolation { .. }
+            | TodoWarning::UnknownDueDate { .. } => !settings.check_due_only,
+            TodoWarning::Overdue { .. } | TodoWarning::DueSoon { .. } => {
+                !settings.check_format_only
+            }
+        }
+    }
+
+    pub fn severity(&self, severities: &Severities) -> Severity {
+        match self {
+            TodoWarning::InvalidFormat { .. } => severities.invalid_format,
+            TodoWarning::Stale { .. } => severities.stale,
+            TodoWarning::Overdue { days_overdue, .. } => {
+                if *days_overdue <= severities.grace_period_days as i64 {
+                    severities.grace_period
+                } else {
+                    severities.overdue
+                }
+            }
+            TodoWarning::DueSoon { .. } => severities.due_soon,
+            TodoWarning::PolicyViolation { .. } => severities.policy_violation,
+            TodoWarning::UnknownDueDate { .. } => severities.unknown_due_date,
+        }
+    }
+
+    /// Whether the due date was derived from the age of the TODO instead of written in it.
+    pub fn has_implicit_due_date(&self) -> bool {
+        match self {
+            TodoWarning::Overdue {
+                implicit_due_date, ..
+            } => *implicit_due_date,
+            TodoWarning::DueSoon {
+                implicit_due_date, ..
+            } => *implicit_due_date,
+            _ => false,
+        }
+    }
+
+    /// Days since the TODO was introduced, when age tracking is enabled.
+    pub fn age_days(&self) -> Option<i64> {
+        match self {
+            TodoWarning::InvalidFormat { age_days, .. } => *age_days,
+            TodoWarning::Stale { age_days, .. } => Some(*age_days),
+            TodoWarning::Overdue { age_days, .. } => *age_days,
+            TodoWarning::DueSoon { age_days, .. } => *age_days,
+            TodoWarning::PolicyViolation { age_days, .. } => *age_days,
+            TodoWarning::UnknownDueDate { age_days, .. } => *age_days,
         }
     }
 }
 
+/// The warnings of a file, along with the settings of the rule that matched it.
+#[derive(Debug)]
+pub struct FileReport {
+    pub warnings: Vec<TodoWarning>,
+    pub settings: RuleSettings,
+}
+
+/// The parts of a comment with a valid format, as byte ranges of the comment.
+#[derive(Debug, Clone)]
+pub struct FormatMatch {
+    pub format: TodoFormat,
+    pub keyword: Range<usize>,
+    pub due_date: Option<Range<usize>>,
+    pub owner: Range<usize>,
+    pub description: Range<usize>,
+}
+
 pub struct TodoAnalyzer<'config> {
     parser: Parser,
     todo_regex: Regex,
-    todo_format_regex: Regex,
+    /// The regex of each format, enabled or not.
+    format_regexes: Vec<(TodoFormat, Regex)>,
+    keywords: Vec<String>,
+    /// The enabled formats, tried in order.
+    formats: Vec<TodoFormat>,
+    date_formats: DateFormats,
+    deadlines: Deadlines,
     language_configs: &'config HashMap<String, LanguageConfig>,
 }
 
@@ -59,32 +281,199 @@ impl<'config> TodoAnalyzer<'config> {
     pub fn new(language_configs: &'config HashMap<String, LanguageConfig>) -> Result<Self> {
         let parser = Parser::new();
 
-        let todo_regex = Regex::new(r"(?i)(TODO|FIXME)(:)?|@todo(:)?")?;
-        let todo_format_regex =
-            Regex::new(r"(?i)(TODO|FIXME):\s*(\d{4}-\d{2}-\d{2})\s+@([\w.-]+)\s*(.*)")?;
-
-        Ok(Self {
+        let mut analyzer = Self {
             parser,
-            todo_regex,
-            todo_format_regex,
+            todo_regex: Regex::new("")?,
+            format_regexes: Vec::new(),
+            keywords: Vec::new(),
+            formats: DEFAULT_FORMATS.to_vec(),
+            date_formats: DateFormats::new(&DEFAULT_DATE_FORMATS.map(String::from))?,
+            deadlines: Deadlines::default(),
             language_configs,
+        };
+        let keywords: Vec<String> = DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect();
+        analyzer.set_keywords(&keywords)?;
+
+        Ok(analyzer)
+    }
+
+    /// Sets the words that mark a comment as a TODO, matched case-insensitively.
+    pub fn set_keywords(&mut self, keywords: &[String]) -> Result<()> {
+        if self.keywords == keywords {
+            return Ok(());
+        }
+        if keywords.is_empty() {
+            anyhow::bail!("At least one TODO keyword is required");
+        }
+
+        self.keywords = keywords.to_vec();
+        self.compile_regexes()
+    }
+
+    /// Sets the conventions that TODOs with a valid format follow.
+    pub fn set_formats(&mut self, formats: &[TodoFormat]) -> Result<()> {
+        if self.formats == formats {
+            return Ok(());
+        }
+        if formats.is_empty() {
+            anyhow::bail!("At least one TODO format is required");
+        }
+
+        self.formats = formats.to_vec();
+        Ok(())
+    }
+
+    /// Sets the chrono formats that due dates of a single day can be written in.
+    pub fn set_date_formats(&mut self, date_formats: &[String]) -> Result<()> {
+        if self.date_formats.formats() == date_formats {
+            return Ok(());
+        }
+
+        self.date_formats = DateFormats::new(date_formats)?;
+        self.compile_regexes()
+    }
+
+    pub fn date_formats(&self) -> &DateFormats {
+        &self.date_formats
+    }
+
+    pub fn set_deadlines(&mut self, deadlines: Deadlines) {
+        self.deadlines = deadlines;
+    }
+
+    fn compile_regexes(&mut self) -> Result<()> {
+        let keywords_pattern = self
+            .keywords
+            .iter()
+            .map(|keyword| regex::escape(keyword))
+            .collect::<Vec<_>>()
+            .join("|");
+        self.todo_regex = Regex::new(&format!(r"(?i)({})(:)?|@todo(:)?", keywords_pattern))?;
+
+        let date = format!(
+            "(?P<due_date>{}|{})",
+            self.date_formats.pattern(),
+            CALENDAR_NAME_PATTERN
+        );
+        // The date after the description's colon in the google format can't be a week, month,
+        // quarter or year, which would also match numbers at the start of the description
+        let day = format!(
+            "(?P<due_date>{}|{})",
+            self.date_formats.day_pattern(),
+            CALENDAR_NAME_PATTERN
+        );
+        let owner = r"(?P<owner>[\w.-]+)";
+        self.format_regexes = TodoFormat::ALL
+            .iter()
+            .map(|format| {
+                let pattern = match format {
+                    TodoFormat::Standard => format!(
+                        r"(?P<keyword>{}):\s*{}\s+@{}\s*",
+                        keywords_pattern, date, owner
+                    ),
+                    TodoFormat::DocTag => format!(
+                        r"@(?P<keyword>todo|{}):?\s+{}\s+@{}\s*",
+                        keywords_pattern, date, owner
+                    ),
+                    TodoFormat::Google => format!(
+                        r"(?P<keyword>{})\(@?{}\):\s*(?:{}\b\s*)?",
+                        keywords_pattern, owner, day
+                    ),
+                    TodoFormat::Bracketed => format!(
+                        r"(?P<keyword>{}):?\s*\[{}\]\s*\(@?{}\):?\s*",
+                        keywords_pattern, date, owner
+                    ),
+                    TodoFormat::OwnerDate => format!(
+                        r"(?P<keyword>{})\(@?{},\s*{}\):?\s*",
+                        keywords_pattern, owner, date
+                    ),
+                };
+                Ok((
+                    *format,
+                    Regex::new(&format!("(?i){}(?P<description>.*)", pattern))?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    /// Finds the TODO keyword in a comment, returning its range including an `:` after it,
+    /// and the configured keyword it matched, or the first one for `@todo`.
+    pub fn find_keyword(&self, comment: &str) -> Option<(Range<usize>, &str)> {
+        let captures = self.todo_regex.captures(comment)?;
+        let keyword = captures
+            .get(1)
+            .and_then(|matched| {
+                self.keywords
+                    .iter()
+                    .find(|keyword| keyword.eq_ignore_ascii_case(matched.as_str()))
+            })
+            .unwrap_or(&self.keywords[0]);
+        Some((captures.get(0)?.range(), keyword))
+    }
+
+    /// Whether a comment is a TODO in one of the enabled formats.
+    pub fn is_valid_format(&self, comment: &str) -> bool {
+        self.match_format(comment).is_some()
+    }
+
+    /// Takes apart a comment in the first of the enabled formats it matches.
+    pub fn match_format(&self, comment: &str) -> Option<FormatMatch> {
+        self.formats
+            .iter()
+            .find_map(|format| self.match_one_format(*format, comment))
+    }
+
+    /// The first format that a comment would be valid in, if it were enabled.
+    pub fn disabled_format(&self, comment: &str) -> Option<TodoFormat> {
+        TodoFormat::ALL
+            .into_iter()
+            .filter(|format| !self.formats.contains(format))
+            .find(|format| self.match_one_format(*format, comment).is_some())
+    }
+
+    /// Matches a comment against a format. Ambiguous dates and dates that don't exist, like
+    /// `2024-02-30`, don't match, so that they are reported as invalid.
+    fn match_one_format(&self, format: TodoFormat, comment: &str) -> Option<FormatMatch> {
+        let (_, regex) = self.format_regexes.iter().find(|(f, _)| *f == format)?;
+        let captures = regex.captures(comment)?;
+        if let Some(due_date) = captures.name("due_date") {
+            match self.date_formats.parse(due_date.as_str()) {
+                Err(DateError::Ambiguous(_)) => return None,
+                Err(DateError::Invalid) if !is_calendar_name(due_date.as_str()) => return None,
+                _ => {}
+            }
+        }
+        Some(FormatMatch {
+            format,
+            keyword: captures.name("keyword")?.range(),
+            due_date: captures.name("due_date").map(|m| m.range()),
+            owner: captures.name("owner")?.range(),
+            description: captures.name("description")?.range(),
         })
     }
 
     pub fn set_language(&mut self, language: &str) -> Result<()> {
-        if let Some(config) = self.language_configs.get(language) {
-            self.parser.set_language(&config.language)?;
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Unsupported language: {}", language))
-        }
+        let config = self.language_config(language)?;
+        self.parser.set_language(&config.language)?;
+        Ok(())
+    }
+
+    pub fn language_config(&self, language: &str) -> Result<&'config LanguageConfig> {
+        self.language_configs.get(language).ok_or_else(|| {
+            let suggestion = suggest(language, self.language_configs.keys().map(String::as_str));
+            anyhow::anyhow!(
+                "Unsupported language: {}{}",
+                language,
+                did_you_mean(suggestion)
+            )
+        })
     }
 
-    pub fn analyze_file(&mut self, file_path: &Path, language: &str) -> Result<Vec<TodoItem>> {
-        let content = std::fs::read_to_string(file_path)?;
+    pub fn analyze_source(&mut self, content: &str, language: &str) -> Result<Vec<TodoItem>> {
         let tree = self
             .parser
-            .parse(&content, None)
+            .parse(content, None)
             .ok_or_else(|| anyhow::anyhow!("Failed to parse file"))?;
 
         // Get the comment tokens for the specified language
@@ -98,7 +487,7 @@ impl<'config> TodoAnalyzer<'config> {
 
         for comment_query in comment_queries {
             let mut cursor = QueryCursor::new();
-            let query = Query::new(&self.parser.language().unwrap(), &comment_query)?;
+            let query = Query::new(&self.parser.language().unwrap(), comment_query)?;
             let mut matches = cursor.matches(&query, tree.root_node(), content.as_bytes());
 
             while let Some(match_) = matches.next() {
@@ -110,36 +499,51 @@ impl<'config> TodoAnalyzer<'config> {
                     let todo_text = comment_text.trim();
 
                     // Parse TODO format
-                    if let Some(captures) = self.todo_format_regex.captures(comment_text) {
-                        let due_date = captures
-                            .get(2)
-                            .map(|m| {
-                                NaiveDateTime::parse_from_str(
-                                    &format!("{} 00:00:00", m.as_str()),
-                                    "%Y-%m-%d %H:%M:%S",
-                                )
-                                .ok()
-                                .map(|dt| Local.from_local_datetime(&dt).unwrap())
-                            })
-                            .flatten();
+                    if let Some(format_match) = self.match_format(comment_text) {
+                        let due_text = format_match.due_date.map(|range| &comment_text[range]);
+                        let (due_date, due_precision) = due_text
+                            .and_then(|due_text| self.date_formats.parse(due_text).ok())
+                            .map_or((None, DatePrecision::Day), |(due, precision)| {
+                                (self.deadlines.due(due, precision), precision)
+                            });
+                        let due_name = due_text
+                            .filter(|due_text| is_calendar_name(due_text))
+                            .map(String::from);
 
-                        let owner = captures.get(3).map(|m| m.as_str().to_string());
+                        let owner = Some(comment_text[format_match.owner].to_string());
+                        let description = comment_text[format_match.description].trim().to_string();
 
                         todos.push(TodoItem {
                             text: todo_text.to_string(),
                             due_date,
+                            due_precision,
+                            due_name,
                             owner,
+                            description,
                             line_number: comment_node.start_position().row + 1,
                             is_valid_format: true,
+                            introduced_at: None,
+                            implicit_due_date: false,
+                            byte_range: comment_node.byte_range(),
+                            hint: None,
                         });
                     } else {
                         // Invalid format
                         todos.push(TodoItem {
                             text: todo_text.to_string(),
                             due_date: None,
+                            due_precision: DatePrecision::Day,
+                            due_name: None,
                             owner: None,
+                            description: todo_text.to_string(),
                             line_number: comment_node.start_position().row + 1,
                             is_valid_format: false,
+                            introduced_at: None,
+                            implicit_due_date: false,
+                            byte_range: comment_node.byte_range(),
+                            hint: LooseTodo::parse(self, comment_text)
+                                .ok()
+                                .map(|todo| todo.hint()),
                         });
                     }
                 }
@@ -149,33 +553,110 @@ impl<'config> TodoAnalyzer<'config> {
         Ok(todos)
     }
 
-    pub fn check_todos(&self, todos: &[TodoItem], cli: &Cli) -> Vec<TodoWarning> {
-        let now = Local::now();
+    pub fn check_todos(
+        &self,
+        todos: &[TodoItem],
+        settings: &RuleSettings,
+        now: DateTime<FixedOffset>,
+        config: &Config,
+    ) -> Vec<TodoWarning> {
+        let calendar = &config.calendar;
         let mut warnings = Vec::new();
 
         for todo in todos {
-            if !todo.is_valid_format {
-                warnings.push(TodoWarning::InvalidFormat {
-                    line_number: todo.line_number,
-                    comment: todo.text.clone(),
+            let age_days = todo
+                .introduced_at
+                .map(|introduced_at| (now - introduced_at).num_days().max(0));
+
+            // Sprints and milestones are due at the end date of the calendar
+            let due_date = match &todo.due_name {
+                Some(due_name) => {
+                    let end_date = calendar.resolve(due_name).and_then(|end_date| {
+                        self.deadlines.due(end_date.into(), DatePrecision::Day)
+                    });
+                    if end_date.is_none() {
+                        warnings.push(TodoWarning::UnknownDueDate {
+                            line_number: todo.line_number,
+                            due_name: due_name.clone(),
+                            owner: todo.owner.clone(),
+                            comment: todo.text.clone(),
+                            message: calendar.unknown_name_message(due_name),
+                            age_days,
+                        });
+                    }
+                    end_date
+                }
+                None => todo.due_date,
+            };
+
+            // TODOs without a valid format can still have an implicit due date
+            if let Some(due_date) = due_date {
+                // Days are counted in the timezone of the due date
+                let days_until_due = (due_date.date_naive() - now.date_naive()).num_days();
+                let business_days_until_due = config.business_days.as_ref().map(|business_days| {
+                    business_days.between(now.date_naive(), due_date.date_naive())
                 });
-            } else if let Some(due_date) = todo.due_date {
-                let days_until_due = (due_date - now).num_days();
 
                 if due_date < now {
                     warnings.push(TodoWarning::Overdue {
                         line_number: todo.line_number,
                         due_date,
+                        due_precision: todo.due_precision,
+                        due_name: todo.due_name.clone(),
                         owner: todo.owner.clone(),
                         comment: todo.text.clone(),
+                        days_overdue: -days_until_due,
+                        age_days,
+                        implicit_due_date: todo.implicit_due_date,
                     });
-                } else if cli.due_in > 0 && days_until_due <= cli.due_in as i64 {
+                } else if settings.due_in > 0
+                    && business_days_until_due.unwrap_or(days_until_due) <= settings.due_in as i64
+                {
                     warnings.push(TodoWarning::DueSoon {
                         line_number: todo.line_number,
                         due_date,
+                        due_precision: todo.due_precision,
+                        due_name: todo.due_name.clone(),
                         owner: todo.owner.clone(),
                         comment: todo.text.clone(),
                         days_until_due,
+                        business_days_until_due,
+                        age_days,
+                        implicit_due_date: todo.implicit_due_date,
+                    });
+                }
+            } else if !todo.is_valid_format {
+                match (age_days, config.parameters.stale_after_days) {
+                    (Some(age_days), Some(stale_after_days))
+                        if age_days > stale_after_days as i64 =>
+                    {
+                        warnings.push(TodoWarning::Stale {
+                            line_number: todo.line_number,
+                            comment: todo.text.clone(),
+                            age_days,
+                        });
+                    }
+                    _ => warnings.push(TodoWarning::InvalidFormat {
+                        line_number: todo.line_number,
+                        comment: todo.text.clone(),
+                        age_days,
+                        hint: todo.hint.clone(),
+                    }),
+                }
+            }
+
+            if todo.is_valid_format {
+                for (name, message) in self.check_policy(todo, due_date, &config.policy, now) {
+                    warnings.push(TodoWarning::PolicyViolation {
+                        line_number: todo.line_number,
+                        due_date,
+                        due_precision: todo.due_precision,
+                        due_name: todo.due_name.clone(),
+                        owner: todo.owner.clone(),
+                        comment: todo.text.clone(),
+                        policy: name,
+                        message,
+                        age_days,
                     });
                 }
             }
@@ -183,4 +664,63 @@ impl<'config> TodoAnalyzer<'config> {
 
         warnings
     }
+
+    /// Returns the name of each policy the TODO violates, along with an explanation.
+    fn check_policy(
+        &self,
+        todo: &TodoItem,
+        due_date: Option<DateTime<FixedOffset>>,
+        policy: &Policy,
+        now: DateTime<FixedOffset>,
+    ) -> Vec<(&'static str, String)> {
+        let mut violations = Vec::new();
+
+        if let (Some(max_due_horizon_days), Some(due_date)) =
+            (policy.max_due_horizon_days, due_date)
+        {
+            if (due_date - now).num_days() > max_due_horizon_days as i64 {
+                violations.push((
+                    "max_due_horizon_days",
+                    format!(
+                        "Due date is more than {} days in the future",
+                        max_due_horizon_days
+                    ),
+                ));
+            }
+        }
+
+        if let Some(min_description_length) = policy.min_description_length {
+            if todo.description.chars().count() < min_description_length {
+                violations.push((
+                    "min_description_length",
+                    format!(
+                        "Description is shorter than {} characters",
+                        min_description_length
+                    ),
+                ));
+            }
+        }
+
+        if let Some(issue_reference_regex) = &policy.issue_reference_regex {
+            if !issue_reference_regex.is_match(&todo.description) {
+                violations.push((
+                    "require_issue_reference",
+                    "Description doesn't reference an issue".to_string(),
+                ));
+            }
+        }
+
+        if policy.forbid_past_date_on_new_todos {
+            if let (Some(due_date), Some(introduced_at)) = (due_date, todo.introduced_at) {
+                if due_date.date_naive() < introduced_at.date_naive() {
+                    violations.push((
+                        "forbid_past_date_on_new_todos",
+                        "Due date was already in the past when the TODO was written".to_string(),
+                    ));
+                }
+            }
+        }
+
+        violations
+    }
 }
@@ -0,0 +1,617 @@
+use crate::business_days::{parse_holiday, parse_weekend_day, read_holidays};
+use crate::calendar::Calendar;
+use crate::cli::ValidateConfigArgs;
+use crate::config::{embedded_sections, interpolate_env, Config, DueAt, FRAGMENT_FILE_NAME};
+use crate::dates::{date_format_pattern, Deadlines};
+use crate::expr::Expr;
+use crate::filter::FIELDS;
+use crate::schema::{config_schema, fragment_schema};
+use serde_json::Value;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+use walkdir::WalkDir;
+
+const EDITOR_URL_PLACEHOLDERS: [&str; 2] = ["%%file%%", "%%line%%"];
+
+/// A mistake in a config file, with the line and column it was found at when known.
+struct Problem {
+    file: PathBuf,
+    position: Option<(usize, usize)>,
+    message: String,
+}
+
+struct SourceFile<'a> {
+    path: &'a Path,
+    content: &'a str,
+}
+
+struct Validator {
+    config_schema: Value,
+    base_dir: PathBuf,
+    visited: Vec<PathBuf>,
+    problems: Vec<Problem>,
+}
+
+/// Checks the config file, the files it extends or includes and the fragments in the rule
+/// paths. Returns whether any problems were found.
+pub fn run(args: &ValidateConfigArgs) -> anyhow::Result<bool> {
+    if args.schema {
+        println!("{}", serde_json::to_string_pretty(&config_schema())?);
+        return Ok(false);
+    }
+
+    let config_file_path = Config::locate(args.config_file_path.as_deref())?;
+    let mut validator = Validator {
+        config_schema: config_schema(),
+        base_dir: Config::base_dir(&config_file_path)?,
+        visited: Vec::new(),
+        problems: Vec::new(),
+    };
+    validator.validate_config_file(&config_file_path);
+
+    // Problems that only show up once all files are merged, such as missing rules, are
+    // reported without a position
+    if validator.problems.is_empty() {
+        match Config::from_file(&config_file_path) {
+            Ok(config) => validator.validate_fragments(&config, &config_file_path),
+            Err(e) => validator.problems.push(Problem {
+                file: config_file_path.clone(),
+                position: None,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    let mut problems = validator.problems;
+    problems.sort_by(|a, b| (&a.file, a.position).cmp(&(&b.file, b.position)));
+
+    let current_dir = std::env::current_dir()?;
+    for problem in &problems {
+        let file = problem
+            .file
+            .strip_prefix(&current_dir)
+            .unwrap_or(&problem.file);
+        match problem.position {
+            Some((line, column)) => {
+                println!(
+                    "{}:{}:{}: {}",
+                    file.display(),
+                    line,
+                    column,
+                    problem.message
+                )
+            }
+            None => println!("{}: {}", file.display(), problem.message),
+        }
+    }
+
+    let config_file = config_file_path
+        .strip_prefix(&current_dir)
+        .unwrap_or(&config_file_path);
+    if problems.is_empty() {
+        println!("{} is valid", config_file.display());
+    } else {
+        println!(
+            "Found {} problem{} in {}",
+            problems.len(),
+            if problems.len() == 1 { "" } else { "s" },
+            config_file.display()
+        );
+    }
+
+    Ok(!problems.is_empty())
+}
+
+/// Returns the candidate closest to `value`, if it is close enough to be a likely typo.
+pub fn suggest<'a>(value: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::damerau_levenshtein(value, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn did_you_mean(suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean \"{}\"?", suggestion),
+        None => String::new(),
+    }
+}
+
+impl Validator {
+    fn validate_config_file(&mut self, path: &Path) {
+        let content = match path
+            .canonicalize()
+            .and_then(|canonical_path| Ok((canonical_path, std::fs::read_to_string(path)?)))
+        {
+            Ok((canonical_path, content)) => {
+                // Cycles are reported when the config is merged
+                if self.visited.contains(&canonical_path) {
+                    return;
+                }
+                self.visited.push(canonical_path);
+                content
+            }
+            Err(e) => {
+                self.report(path, None, format!("Failed to read config file: {}", e));
+                return;
+            }
+        };
+        let file = SourceFile {
+            path,
+            content: &content,
+        };
+
+        let document = match DeTable::parse(&content) {
+            Ok(document) => document,
+            Err(e) => {
+                file.report(self, e.span(), e.message().to_string());
+                return;
+            }
+        };
+        let root = Spanned::new(document.span(), DeValue::Table(document.into_inner()));
+
+        let (table, key_path) = match embedded_sections(path) {
+            Some(sections) => {
+                let found = sections.iter().find_map(|section| {
+                    section
+                        .iter()
+                        .try_fold(&root, |value, key| table_get(value.get_ref(), key))
+                        .map(|value| (value, section.join(".")))
+                });
+                match found {
+                    Some(found) => found,
+                    None => {
+                        self.report(path, None, "No todo-reminder section".to_string());
+                        return;
+                    }
+                }
+            }
+            None => (&root, String::new()),
+        };
+
+        let schema = self.config_schema.clone();
+        self.check_schema(&file, table, &schema, &key_path);
+        self.check_values(&file, table.get_ref(), &key_path);
+    }
+
+    /// Checks the values that are valid according to the schema, but can't work.
+    fn check_values(&mut self, file: &SourceFile, table: &DeValue, key_path: &str) {
+        let key = |name: &str| join_key(key_path, name);
+
+        if let Some(parameters) = table_get(table, "parameters") {
+            if let Some(editor_url) = table_get(parameters.get_ref(), "editor_url") {
+                if let Some(url) = editor_url.get_ref().as_str() {
+                    self.check_editor_url(file, url, editor_url.span());
+                }
+            }
+            self.check_date_formats(file, parameters.get_ref(), &key("parameters"));
+            if let Some(calendar) = table_get(parameters.get_ref(), "calendar") {
+                if let Some(path) = calendar.get_ref().as_str() {
+                    let loaded = interpolate_env(path)
+                        .and_then(|path| Calendar::from_file(&self.base_dir, &path));
+                    if let Err(e) = loaded {
+                        file.report(self, Some(calendar.span()), e.to_string());
+                    }
+                }
+            }
+            if let Some(timezone) = table_get(parameters.get_ref(), "timezone") {
+                if let Some(name) = timezone.get_ref().as_str() {
+                    if let Err(e) = Deadlines::new(Some(name), DueAt::default()) {
+                        file.report(self, Some(timezone.span()), e.to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(business_days) = table_get(table, "business_days") {
+            let business_days = business_days.get_ref();
+            for day in array_strings(business_days, "weekend") {
+                if let Err(e) = parse_weekend_day(day.get_ref()) {
+                    file.report(self, Some(day.span()), e.to_string());
+                }
+            }
+            for holiday in array_strings(business_days, "holidays") {
+                if let Err(e) = parse_holiday(holiday.get_ref()) {
+                    file.report(self, Some(holiday.span()), e.to_string());
+                }
+            }
+            if let Some(holidays_file) = table_get(business_days, "holidays_file") {
+                if let Some(path) = holidays_file.get_ref().as_str() {
+                    if let Err(e) = read_holidays(&self.base_dir.join(path)) {
+                        file.report(self, Some(holidays_file.span()), e.to_string());
+                    }
+                }
+            }
+        }
+
+        let rules = table_get(table, "rules").and_then(|rules| rules.get_ref().as_array());
+        for (index, rule) in rules.into_iter().flatten().enumerate() {
+            let rule_key = format!("{}[{}]", key("rules"), index);
+            self.check_date_formats(file, rule.get_ref(), &rule_key);
+
+            for path in array_strings(rule.get_ref(), "paths") {
+                let interpolated = match interpolate_env(path.get_ref()) {
+                    Ok(interpolated) => interpolated,
+                    Err(e) => {
+                        file.report(self, Some(path.span()), e.to_string());
+                        continue;
+                    }
+                };
+                if !self.base_dir.join(&interpolated).is_dir() {
+                    file.report(
+                        self,
+                        Some(path.span()),
+                        format!(
+                            "Directory \"{}\" in {}.paths does not exist",
+                            interpolated, rule_key
+                        ),
+                    );
+                }
+            }
+
+            for extension in array_strings(rule.get_ref(), "file_extensions") {
+                let extension_str = extension.get_ref();
+                let message = if extension_str.starts_with('.') {
+                    "write it without the leading dot"
+                } else if extension_str.is_empty()
+                    || extension_str
+                        .chars()
+                        .any(|c| c.is_whitespace() || "/\\*?".contains(c))
+                {
+                    "it must be a plain extension such as \"rs\""
+                } else {
+                    continue;
+                };
+                file.report(
+                    self,
+                    Some(extension.span()),
+                    format!(
+                        "Invalid file extension \"{}\" in {}.file_extensions, {}",
+                        extension_str, rule_key, message
+                    ),
+                );
+            }
+        }
+
+        let views = table_get(table, "views").and_then(|views| views.get_ref().as_table());
+        for (name, view) in views.into_iter().flatten() {
+            let filter = match table_get(view.get_ref(), "filter") {
+                Some(filter) => filter,
+                None => continue,
+            };
+            if let Some(expression) = filter.get_ref().as_str() {
+                if let Err(e) = Expr::parse(expression, &FIELDS) {
+                    file.report(
+                        self,
+                        Some(filter.span()),
+                        format!(
+                            "Invalid filter in {}: {}",
+                            join_key(&key("views"), name.get_ref()),
+                            e
+                        ),
+                    );
+                }
+            }
+        }
+
+        let dir = file.path.parent().unwrap();
+        let extends = table_get(table, "extends").and_then(|extends| {
+            extends
+                .get_ref()
+                .as_str()
+                .map(|path| Spanned::new(extends.span(), path.to_string()))
+        });
+        for path in extends.into_iter().chain(array_strings(table, "include")) {
+            match interpolate_env(path.get_ref()) {
+                Ok(interpolated) if dir.join(&interpolated).is_file() => {
+                    self.validate_config_file(&dir.join(interpolated));
+                }
+                Ok(interpolated) => file.report(
+                    self,
+                    Some(path.span()),
+                    format!("Config file \"{}\" does not exist", interpolated),
+                ),
+                Err(e) => file.report(self, Some(path.span()), e.to_string()),
+            }
+        }
+    }
+
+    fn check_editor_url(&mut self, file: &SourceFile, url: &str, span: Range<usize>) {
+        let mut rest = url;
+        while let Some(start) = rest.find("%%") {
+            let after_start = &rest[start + 2..];
+            let length = match after_start.find("%%") {
+                Some(length) => length,
+                None => break,
+            };
+            let placeholder = &rest[start..start + length + 4];
+            if !EDITOR_URL_PLACEHOLDERS.contains(&placeholder) {
+                file.report(
+                    self,
+                    Some(span.clone()),
+                    format!(
+                        "Unknown placeholder \"{}\" in parameters.editor_url{}",
+                        placeholder,
+                        did_you_mean(suggest(placeholder, EDITOR_URL_PLACEHOLDERS))
+                    ),
+                );
+            }
+            rest = &after_start[length + 2..];
+        }
+
+        if !url.contains("%%file%%") {
+            file.report(
+                self,
+                Some(span),
+                "parameters.editor_url has no %%file%% placeholder".to_string(),
+            );
+        }
+    }
+
+    fn check_date_formats(&mut self, file: &SourceFile, table: &DeValue, key_path: &str) {
+        for date_format in array_strings(table, "date_formats") {
+            if let Err(e) = date_format_pattern(date_format.get_ref()) {
+                file.report(
+                    self,
+                    Some(date_format.span()),
+                    format!(
+                        "Invalid date format \"{}\" in {}: {}",
+                        date_format.get_ref(),
+                        join_key(key_path, "date_formats"),
+                        e
+                    ),
+                );
+            }
+        }
+    }
+
+    fn validate_fragments(&mut self, config: &Config, config_file_path: &Path) {
+        let config_file_path = config_file_path.canonicalize().ok();
+        let schema = fragment_schema();
+
+        for rule in &config.rules {
+            for rule_path in &rule.paths {
+                let fragments = WalkDir::new(self.base_dir.join(rule_path))
+                    .into_iter()
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.file_name() == FRAGMENT_FILE_NAME);
+
+                for entry in fragments {
+                    let path = entry.path();
+                    let canonical_path = match path.canonicalize() {
+                        Ok(canonical_path) => canonical_path,
+                        Err(_) => continue,
+                    };
+                    // Rules can share directories, and the config file can be inside them
+                    if Some(&canonical_path) == config_file_path.as_ref()
+                        || self.visited.contains(&canonical_path)
+                    {
+                        continue;
+                    }
+                    self.visited.push(canonical_path);
+                    let content = match std::fs::read_to_string(path) {
+                        Ok(content) => content,
+                        Err(e) => {
+                            self.report(path, None, format!("Failed to read fragment: {}", e));
+                            continue;
+                        }
+                    };
+                    let file = SourceFile {
+                        path,
+                        content: &content,
+                    };
+                    match DeTable::parse(&content) {
+                        Ok(document) => {
+                            let root = Spanned::new(
+                                document.span(),
+                                DeValue::Table(document.into_inner()),
+                            );
+                            self.check_schema(&file, &root, &schema, "");
+                            self.check_date_formats(&file, root.get_ref(), "");
+                        }
+                        Err(e) => file.report(self, e.span(), e.message().to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks the keys, types and allowed values of a TOML value against a JSON Schema.
+    fn check_schema(
+        &mut self,
+        file: &SourceFile,
+        value: &Spanned<DeValue>,
+        schema: &Value,
+        key_path: &str,
+    ) {
+        let schema = match schema["$ref"].as_str() {
+            Some(reference) => self
+                .config_schema
+                .pointer(reference.trim_start_matches('#'))
+                .cloned()
+                .unwrap_or_default(),
+            None => schema.clone(),
+        };
+
+        if let Some(expected) = schema["type"].as_str() {
+            let actual = type_name(value.get_ref());
+            if actual != expected {
+                file.report(
+                    self,
+                    Some(value.span()),
+                    format!(
+                        "{} must be {}, found {}",
+                        key_path,
+                        toml_type_name(expected),
+                        toml_type_name(actual)
+                    ),
+                );
+                return;
+            }
+        }
+
+        if let (Some(allowed), Some(actual)) = (schema["enum"].as_array(), value.get_ref().as_str())
+        {
+            let allowed: Vec<&str> = allowed.iter().filter_map(Value::as_str).collect();
+            if !allowed.contains(&actual) {
+                file.report(
+                    self,
+                    Some(value.span()),
+                    format!(
+                        "Invalid value \"{}\" for {}{}",
+                        actual,
+                        key_path,
+                        match suggest(actual, allowed.iter().copied()) {
+                            Some(suggestion) => did_you_mean(Some(suggestion)),
+                            // Only list short sets of values, not every language
+                            None if allowed.len() <= 5 => format!(
+                                ", expected one of {}",
+                                allowed
+                                    .iter()
+                                    .map(|value| format!("\"{}\"", value))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                            None => String::new(),
+                        }
+                    ),
+                );
+            }
+        }
+
+        match value.get_ref() {
+            DeValue::Table(table) => {
+                for (key, item) in table.iter() {
+                    let key_name: &str = key.get_ref();
+                    let item_path = join_key(key_path, key_name);
+                    if let Some(item_schema) = schema["properties"].get(key_name) {
+                        self.check_schema(file, item, item_schema, &item_path);
+                    } else if schema["additionalProperties"].is_object() {
+                        self.check_schema(file, item, &schema["additionalProperties"], &item_path);
+                    } else if schema["additionalProperties"] == Value::Bool(false) {
+                        let known_keys = schema["properties"]
+                            .as_object()
+                            .into_iter()
+                            .flat_map(|properties| properties.keys().map(String::as_str));
+                        file.report(
+                            self,
+                            Some(key.span()),
+                            format!(
+                                "Unknown key \"{}\"{}{}",
+                                key_name,
+                                if key_path.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!(" in {}", key_path)
+                                },
+                                did_you_mean(suggest(key_name, known_keys))
+                            ),
+                        );
+                    }
+                }
+
+                let required = schema["required"].as_array().into_iter().flatten();
+                for required_key in required.filter_map(Value::as_str) {
+                    if table_get(value.get_ref(), required_key).is_none() {
+                        file.report(
+                            self,
+                            Some(value.span()),
+                            format!("{} is missing \"{}\"", key_path, required_key),
+                        );
+                    }
+                }
+            }
+            DeValue::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let item_path = format!("{}[{}]", key_path, index);
+                    self.check_schema(file, item, &schema["items"], &item_path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn report(&mut self, file: &Path, position: Option<(usize, usize)>, message: String) {
+        self.problems.push(Problem {
+            file: file.to_path_buf(),
+            position,
+            message,
+        });
+    }
+}
+
+impl SourceFile<'_> {
+    fn report(&self, validator: &mut Validator, span: Option<Range<usize>>, message: String) {
+        let position = span.map(|span| line_and_column(self.content, span.start));
+        validator.report(self.path, position, message);
+    }
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+fn table_get<'a, 'i>(value: &'a DeValue<'i>, key: &str) -> Option<&'a Spanned<DeValue<'i>>> {
+    value.as_table().and_then(|table| {
+        table
+            .iter()
+            .find(|(name, _)| name.get_ref() == key)
+            .map(|(_, value)| value)
+    })
+}
+
+/// Returns the strings of an array in a table, skipping values of other types.
+fn array_strings(table: &DeValue, key: &str) -> Vec<Spanned<String>> {
+    table_get(table, key)
+        .and_then(|value| value.get_ref().as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            item.get_ref()
+                .as_str()
+                .map(|s| Spanned::new(item.span(), s.to_string()))
+        })
+        .collect()
+}
+
+fn join_key(key_path: &str, name: &str) -> String {
+    if key_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", key_path, name)
+    }
+}
+
+fn type_name(value: &DeValue) -> &'static str {
+    match value {
+        DeValue::String(_) => "string",
+        DeValue::Integer(_) => "integer",
+        DeValue::Float(_) => "number",
+        DeValue::Boolean(_) => "boolean",
+        DeValue::Datetime(_) => "datetime",
+        DeValue::Array(_) => "array",
+        DeValue::Table(_) => "object",
+    }
+}
+
+/// Describes a JSON Schema type in TOML terms.
+fn toml_type_name(json_type: &str) -> &str {
+    match json_type {
+        "string" => "a string",
+        "integer" => "an integer",
+        "number" => "a float",
+        "boolean" => "a boolean",
+        "datetime" => "a date",
+        "array" => "an array",
+        "object" => "a table",
+        other => other,
+    }
+}
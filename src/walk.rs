@@ -0,0 +1,332 @@
+use crate::config::{Config, Fragment, Rule, RuleSettings, FRAGMENT_FILE_NAME};
+use crate::fragment::FragmentStack;
+use crate::git;
+use crate::todo_analyzer::TodoAnalyzer;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A file matched by a rule.
+pub struct SourceFile<'a> {
+    pub path: &'a Path,
+    /// The path relative to the config file.
+    pub relative_path: String,
+    pub rule_index: usize,
+    pub rule: &'a Rule,
+    /// The settings of the rule and the fragments of the file's directories.
+    pub settings: &'a RuleSettings,
+    pub content: String,
+}
+
+/// The files that `walk_rules` visits.
+pub enum Tree {
+    /// The files on disk.
+    WorkingTree,
+    /// The files of a git revision, read with `git show`. Its paths are resolved from the
+    /// canonical repository root.
+    Revision {
+        repo_root: PathBuf,
+        rev: String,
+        /// The absolute paths of the files of the revision.
+        files: Vec<PathBuf>,
+    },
+}
+
+impl Tree {
+    pub fn revision(repo_root: &Path, rev: &str) -> Result<Self> {
+        let repo_root = repo_root.canonicalize()?;
+        let mut files: Vec<PathBuf> = git::list_files(&repo_root, rev)?
+            .iter()
+            .map(|file| repo_root.join(file))
+            .collect();
+        files.sort();
+        Ok(Tree::Revision {
+            repo_root,
+            rev: rev.to_string(),
+            files,
+        })
+    }
+
+    /// Resolves `.` and `..` in the paths of a revision, which are compared with the listed
+    /// files rather than walked on disk.
+    fn resolve(&self, path: PathBuf) -> PathBuf {
+        match self {
+            Tree::WorkingTree => path,
+            Tree::Revision { .. } => normalize_path(&path),
+        }
+    }
+
+    /// The directories and files under `root` in depth-first order. `None` if `root` isn't
+    /// a directory.
+    fn entries(&self, root: &Path) -> Option<Entries> {
+        match self {
+            Tree::WorkingTree => root
+                .is_dir()
+                .then(|| Entries::WorkingTree(WalkDir::new(root).into_iter())),
+            Tree::Revision { files, .. } => {
+                let mut entries = BTreeMap::new();
+                for file in files.iter().filter(|file| file.starts_with(root)) {
+                    entries.insert(file.clone(), false);
+                    for dir in file.ancestors().skip(1) {
+                        if !dir.starts_with(root) {
+                            break;
+                        }
+                        entries.insert(dir.to_path_buf(), true);
+                    }
+                }
+                // The sorted paths list each directory right before its contents
+                (!entries.is_empty()).then(|| Entries::Revision {
+                    entries: entries.into_iter().collect::<Vec<_>>().into_iter(),
+                    last_dir: None,
+                    skipped: None,
+                })
+            }
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<String> {
+        match self {
+            Tree::WorkingTree => Ok(std::fs::read_to_string(path)?),
+            Tree::Revision { repo_root, rev, .. } => {
+                let file = path.strip_prefix(repo_root)?.to_string_lossy();
+                git::show_file(repo_root, rev, &file)
+            }
+        }
+    }
+
+    /// The fragment of a directory, if there is one. `config_file_path` is the canonical path
+    /// of the config file, which is not treated as a fragment when it is inside a rule path.
+    fn fragment(&self, dir: &Path, config_file_path: &Path) -> Result<Option<Fragment>> {
+        let path = dir.join(FRAGMENT_FILE_NAME);
+        match self {
+            Tree::WorkingTree => {
+                if !path.is_file() || path.canonicalize()? == config_file_path {
+                    return Ok(None);
+                }
+                Fragment::from_file(&path).map(Some)
+            }
+            Tree::Revision { files, .. } => {
+                if path == config_file_path || files.binary_search(&path).is_err() {
+                    return Ok(None);
+                }
+                Fragment::parse(&self.read(&path)?, &path).map(Some)
+            }
+        }
+    }
+}
+
+/// A depth-first walk of the directories and files of a tree.
+enum Entries {
+    WorkingTree(walkdir::IntoIter),
+    Revision {
+        /// The sorted paths, with `true` for directories.
+        entries: std::vec::IntoIter<(PathBuf, bool)>,
+        last_dir: Option<PathBuf>,
+        skipped: Option<PathBuf>,
+    },
+}
+
+impl Entries {
+    /// The next directory or file, with `true` for directories.
+    fn next(&mut self) -> Option<(PathBuf, bool)> {
+        match self {
+            Entries::WorkingTree(entries) => loop {
+                let entry = match entries.next()? {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                };
+                let file_type = entry.file_type();
+                if file_type.is_dir() || file_type.is_file() {
+                    return Some((entry.into_path(), file_type.is_dir()));
+                }
+            },
+            Entries::Revision {
+                entries,
+                last_dir,
+                skipped,
+            } => {
+                let (path, is_dir) = entries.find(|(path, _)| {
+                    !skipped
+                        .as_ref()
+                        .is_some_and(|skipped| path.starts_with(skipped))
+                })?;
+                *last_dir = is_dir.then(|| path.clone());
+                Some((path, is_dir))
+            }
+        }
+    }
+
+    /// Skips the contents of the directory that was returned last, like
+    /// `walkdir::IntoIter::skip_current_dir`.
+    fn skip_current_dir(&mut self) {
+        match self {
+            Entries::WorkingTree(entries) => entries.skip_current_dir(),
+            Entries::Revision {
+                last_dir, skipped, ..
+            } => *skipped = last_dir.take(),
+        }
+    }
+}
+
+/// A file matched by a rule path, before it is known which rule it belongs to.
+struct Candidate {
+    path: PathBuf,
+    rule_index: usize,
+    /// The number of components of the rule path, more for more specific paths.
+    specificity: usize,
+    settings: RuleSettings,
+}
+
+/// Walks the paths of each rule in the tree, skipping excluded and disabled directories,
+/// and calls `visit` for each file with an extension of the rule. The analyzer is set up
+/// for the language, keywords, formats and date formats of the file.
+///
+/// A file matched by several rules is visited once, with the rule whose path is the most
+/// specific, e.g. `src/legacy` over `src`, or the first of them if their paths are equally
+/// specific. Files are visited in the order of their rules, then in the order of the walk.
+pub fn walk_rules(
+    config: &Config,
+    config_file_path: &Path,
+    tree: &Tree,
+    analyzer: &mut TodoAnalyzer,
+    mut visit: impl FnMut(&mut TodoAnalyzer, SourceFile) -> Result<()>,
+) -> Result<()> {
+    let mut abs_config_file_dir = Config::base_dir(config_file_path)?;
+    if let Tree::Revision { .. } = tree {
+        abs_config_file_dir = abs_config_file_dir.canonicalize()?;
+    }
+    let canonical_config_file_path = config_file_path.canonicalize()?;
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    // The index of the candidate each file is visited with
+    let mut chosen: BTreeMap<PathBuf, usize> = BTreeMap::new();
+
+    for (rule_index, rule) in config.rules.iter().enumerate() {
+        analyzer.set_language(&rule.language)?;
+        let rule_settings = RuleSettings::resolve(&config.parameters, rule);
+        let file_extensions = rule.file_extensions(analyzer.language_config(&rule.language)?);
+
+        for rule_path in &rule.paths {
+            let abs_path = tree.resolve(abs_config_file_dir.join(rule_path));
+            let specificity = normalize_path(&abs_path).components().count();
+
+            let mut entries = match tree.entries(&abs_path) {
+                Some(entries) => entries,
+                None => {
+                    if let Tree::WorkingTree = tree {
+                        eprintln!(
+                            "Directory does not exist or not a folder: {}",
+                            abs_path.display()
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            let mut fragments = FragmentStack::new(
+                rule_settings.clone(),
+                rule.exclude
+                    .iter()
+                    .map(|exclude| tree.resolve(abs_config_file_dir.join(exclude)))
+                    .collect(),
+            );
+            while let Some((path, is_dir)) = entries.next() {
+                fragments.leave(&path);
+
+                if is_dir {
+                    let walked = !fragments.is_excluded(&path)
+                        && match tree.fragment(&path, &canonical_config_file_path)? {
+                            Some(fragment) => fragments.enter(&path, &fragment),
+                            None => true,
+                        };
+                    if !walked {
+                        entries.skip_current_dir();
+                    }
+                    continue;
+                }
+                if fragments.is_excluded(&path) {
+                    continue;
+                }
+                let extension = path.extension().and_then(|ext| ext.to_str());
+
+                if extension.is_none() {
+                    continue;
+                }
+                let ext = extension.unwrap();
+
+                if !file_extensions.contains(&ext.to_string()) {
+                    continue;
+                }
+
+                let key = normalize_path(&path);
+                if let Some(&other) = chosen.get(&key) {
+                    if candidates[other].specificity >= specificity {
+                        continue;
+                    }
+                }
+                chosen.insert(key, candidates.len());
+                candidates.push(Candidate {
+                    path,
+                    rule_index,
+                    specificity,
+                    settings: fragments.settings().clone(),
+                });
+            }
+        }
+    }
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        if chosen.get(&normalize_path(&candidate.path)) != Some(&index) {
+            continue;
+        }
+        let path = candidate.path.as_path();
+        let rule = &config.rules[candidate.rule_index];
+        let settings = &candidate.settings;
+        analyzer.set_language(&rule.language)?;
+        analyzer.set_keywords(&settings.keywords)?;
+        analyzer.set_formats(&settings.formats)?;
+        analyzer.set_date_formats(&settings.date_formats)?;
+
+        let content = match tree.read(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error analyzing file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        visit(
+            analyzer,
+            SourceFile {
+                path,
+                relative_path: path
+                    .strip_prefix(&abs_config_file_dir)?
+                    .display()
+                    .to_string(),
+                rule_index: candidate.rule_index,
+                rule,
+                settings,
+                content,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Resolves `.` and `..` components without touching the filesystem, since the paths
+/// may not exist in the working tree.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -1,10 +1,14 @@
+use std::fs;
+use std::path::Path;
 use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
 
 #[test]
 fn test_main_with_table_format() {
     let output = Command::new("cargo")
         .arg("run")
         .arg("tests/test_files/config.toml")
+        .arg("--today=2024-01-01")
         .arg("--format=table")
         .arg("--no-tty")
         .arg("--exit-zero")
@@ -21,6 +25,7 @@ fn test_main_with_json_format() {
     let output = Command::new("cargo")
         .arg("run")
         .arg("tests/test_files/config.toml")
+        .arg("--today=2024-01-01")
         .arg("--format=json")
         .arg("--exit-zero")
         .output()
@@ -36,6 +41,7 @@ fn test_check_due_only_with_table_format() {
     let output = Command::new("cargo")
         .arg("run")
         .arg("tests/test_files/config.toml")
+        .arg("--today=2024-01-01")
         .arg("--format=table")
         .arg("--no-tty")
         .arg("--exit-zero")
@@ -53,6 +59,7 @@ fn test_check_format_only_with_table_format() {
     let output = Command::new("cargo")
         .arg("run")
         .arg("tests/test_files/config.toml")
+        .arg("--today=2024-01-01")
         .arg("--format=table")
         .arg("--no-tty")
         .arg("--exit-zero")
@@ -64,3 +71,1986 @@ fn test_check_format_only_with_table_format() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
 }
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", "2023-01-01T12:00:00")
+        .env("GIT_COMMITTER_DATE", "2023-01-01T12:00:00")
+        .status()
+        .expect("Failed to execute git");
+    assert!(status.success());
+}
+
+/// Creates a git repository with a single commit containing the config file and the given
+/// files under `src`.
+fn create_repo(parameters: &str, files: &[(&str, &str)]) -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    fs::create_dir(repo.path().join("src")).unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n[parameters]\n{}",
+            parameters
+        ),
+    )
+    .unwrap();
+    for (name, content) in files {
+        fs::write(repo.path().join("src").join(name), content).unwrap();
+    }
+    git(repo.path(), &["init", "-q"]);
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "-q", "-m", "first"]);
+    repo
+}
+
+#[test]
+fn test_diff_between_revisions() {
+    let repo = create_repo(
+        "",
+        &[(
+            "main.rs",
+            "// TODO: 2024-01-01 @alice Remove the workaround\n\
+             // TODO: 2024-01-01 @bob Support more formats\n\
+             // TODO: 2024-01-01 @carol Handle errors\n\
+             // TODO: 2024-01-01 @dave Add tests\n",
+        )],
+    );
+
+    fs::write(
+        repo.path().join("src/main.rs"),
+        "fn main() {}\n\
+         // TODO: 2024-01-01 @alice Remove the workaround\n\
+         // TODO: 2024-03-01 @bob Support more formats\n\
+         // TODO: 2024-01-01 @erin Handle errors\n\
+         // TODO: 2024-02-01 @frank Document the config\n",
+    )
+    .unwrap();
+    git(repo.path(), &["commit", "-q", "-a", "-m", "second"]);
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("diff")
+        .arg("HEAD~1")
+        .arg("HEAD")
+        .arg("--config")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--no-tty")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_diff_with_fragments_and_overlapping_rules() {
+    let repo = create_repo("", &[("main.rs", "fn main() {}\n")]);
+    let config_path = repo.path().join("todo-reminder.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            "{}\n[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n",
+            config
+        ),
+    )
+    .unwrap();
+
+    fs::create_dir(repo.path().join("src/vendor")).unwrap();
+    fs::write(
+        repo.path().join("src/vendor/.todo-reminder.toml"),
+        "disable = true\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("src/vendor/lib.rs"),
+        "// TODO: 2024-01-01 @alice Upstream this\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("src/main.rs"),
+        "fn main() {}\n// TODO: 2024-01-01 @bob Handle errors\n",
+    )
+    .unwrap();
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "-q", "-m", "second"]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg("diff")
+        .arg("HEAD~1")
+        .arg("HEAD")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--no-tty")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("vendor"), "{}", stdout);
+    assert!(
+        stdout.contains("0 TODOs resolved, 1 added, 0 modified (0 postponed, 0 reassigned)"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_stale_todos_with_json_format() {
+    let repo = create_repo(
+        "stale_after_days = 180\n",
+        &[(
+            "main.rs",
+            "// TODO: remove this hack\n// TODO: 2024-01-01 @alice Support more formats\n",
+        )],
+    );
+
+    let run = |today: &str| {
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg(repo.path().join("todo-reminder.toml"))
+            .arg("--format=json")
+            .arg("--exit-zero")
+            .arg(format!("--today={}", today))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let stdout = run("2023-03-01");
+    insta::assert_debug_snapshot!(
+        "stale_todos_before_threshold",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+    let stdout = run("2024-03-01");
+    insta::assert_debug_snapshot!(
+        "stale_todos_after_threshold",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+}
+
+#[test]
+fn test_implicit_due_dates_with_table_format() {
+    let repo = create_repo(
+        "implicit_due_days = 90\n",
+        &[(
+            "main.rs",
+            "// TODO: remove this hack\n\
+             // TODO: 2023-06-15 @alice Support more formats\n\
+             // TODO: 2024-01-01 @bob Handle errors\n",
+        )],
+    );
+    // Files outside of git fall back to their modification time
+    fs::write(
+        repo.path().join("src/lib.rs"),
+        "// TODO: not committed yet\n",
+    )
+    .unwrap();
+    fs::File::options()
+        .write(true)
+        .open(repo.path().join("src/lib.rs"))
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(1675252800))
+        .unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=table")
+        .arg("--no-tty")
+        .arg("--exit-zero")
+        .arg("--due-in=30")
+        .arg("--today=2023-06-01")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_implicit_due_dates_use_commit_time() {
+    let repo = create_repo("implicit_due_days = 90\n", &[("main.rs", "fn main() {}\n")]);
+    // The change was written long before it landed, like a rebased branch
+    fs::write(
+        repo.path().join("src/main.rs"),
+        "fn main() {}\n// TODO: remove this hack\n",
+    )
+    .unwrap();
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo.path())
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-q", "-a", "-m", "second"])
+        .env("GIT_AUTHOR_DATE", "2022-01-01T12:00:00")
+        .env("GIT_COMMITTER_DATE", "2023-03-01T12:00:00")
+        .status()
+        .expect("Failed to execute git");
+    assert!(status.success());
+
+    // Lines that aren't committed yet fall back to the modification time of the file
+    fs::write(
+        repo.path().join("src/main.rs"),
+        "fn main() {}\n// TODO: remove this hack\n// TODO: not committed yet\n",
+    )
+    .unwrap();
+    fs::File::options()
+        .write(true)
+        .open(repo.path().join("src/main.rs"))
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(1675252800))
+        .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=json")
+        .arg("--exit-zero")
+        .arg("--due-in=30")
+        .arg("--today=2023-05-15")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("\"due_date\": \"2023-05-30\""),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\"due_date\": \"2023-05-02\""),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_policy_violations_with_json_format() {
+    let repo = create_repo(
+        "\n[policy]\n\
+         max_due_horizon_days = 365\n\
+         min_description_length = 10\n\
+         require_issue_reference = true\n\
+         forbid_past_date_on_new_todos = true\n",
+        &[(
+            "main.rs",
+            "// TODO: 2023-03-01 @alice Support more formats, see #12\n\
+             // TODO: 2030-01-01 @bob Handle errors in PROJ-123\n\
+             // TODO: 2023-03-01 @carol Fix it\n\
+             // TODO: 2022-06-01 @dave Remove the workaround for #42\n",
+        )],
+    );
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=json")
+        .arg("--exit-zero")
+        .arg("--today=2023-02-01")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_fail_on_severity() {
+    let repo = create_repo(
+        "\n[severity]\ninvalid_format = \"info\"\ngrace_period_days = 14\n",
+        &[(
+            "main.rs",
+            "// TODO: fix this\n// TODO: 2024-01-01 @alice Support more formats\n",
+        )],
+    );
+
+    let exit_code = |args: &[&str]| {
+        Command::new("cargo")
+            .arg("run")
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+            .status
+            .code()
+    };
+
+    assert_eq!(exit_code(&["--today=2023-12-01"]), Some(0));
+    assert_eq!(exit_code(&["--today=2024-01-10"]), Some(1));
+    assert_eq!(
+        exit_code(&["--today=2024-01-10", "--fail-on=error"]),
+        Some(0)
+    );
+    assert_eq!(
+        exit_code(&["--today=2024-02-01", "--fail-on=error"]),
+        Some(1)
+    );
+    assert_eq!(
+        exit_code(&["--today=2024-02-01", "--fail-on=never"]),
+        Some(0)
+    );
+    assert_eq!(exit_code(&["--fail-on=sometimes"]), Some(2));
+}
+
+#[test]
+fn test_budgets_with_table_format() {
+    let repo = create_repo(
+        "\n[budgets.directories.\"src/legacy\"]\nmax_todos = 1\n\
+         \n[budgets.owners.alice]\nmax_overdue = 1\n",
+        &[
+            (
+                "main.rs",
+                "// TODO: fix this\n\
+                 // TODO: 2024-01-01 @alice Support more formats\n\
+                 // TODO: 2024-01-01 @alice Handle errors\n",
+            ),
+            ("lib.rs", "// TODO: 2025-01-01 @bob Document the API\n"),
+        ],
+    );
+    fs::create_dir(repo.path().join("src/legacy")).unwrap();
+    fs::write(
+        repo.path().join("src/legacy/mod.rs"),
+        "// TODO: 2025-01-01 @bob Remove this module\n",
+    )
+    .unwrap();
+    let config = fs::read_to_string(repo.path().join("todo-reminder.toml")).unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        config.replace(
+            "language = \"rust\"\n",
+            "language = \"rust\"\nbudget = { max_todos = 10, max_invalid = 0 }\n",
+        ),
+    )
+    .unwrap();
+
+    let run = |format: &str| {
+        Command::new("cargo")
+            .arg("run")
+            .arg(repo.path().join("todo-reminder.toml"))
+            .arg(format!("--format={}", format))
+            .arg("--no-tty")
+            .arg("--check-due-only")
+            .arg("--today=2024-02-01")
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run("table");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+
+    // The JSON output stays an array of warnings, the budgets have their own format
+    let output = run("json");
+    assert_eq!(output.status.code(), Some(1));
+    let warnings: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(warnings.as_array().map(Vec::len), Some(2));
+
+    let output = run("budgets");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_snapshot!("budgets_with_budgets_format", stdout);
+}
+
+#[test]
+fn test_rule_settings_with_json_format() {
+    let repo = create_repo(
+        "due_in = 7\nteam = \"platform\"\n",
+        &[(
+            "main.rs",
+            "// TODO: fix this\n// TODO: 2024-01-20 @alice Support more formats\n",
+        )],
+    );
+    fs::create_dir(repo.path().join("scripts")).unwrap();
+    fs::write(
+        repo.path().join("scripts/build.rs"),
+        "// TODO: fix this\n// TODO: 2024-01-20 @bob Cache the build\n",
+    )
+    .unwrap();
+    let config = fs::read_to_string(repo.path().join("todo-reminder.toml")).unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "{}\n[[rules]]\npaths = [\"scripts\"]\nlanguage = \"rust\"\n\
+             due_in = 30\ncheck_due_only = true\nteam = \"infra\"\nlabels = [\"ci\"]\n",
+            config
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=json")
+        .arg("--exit-zero")
+        .arg("--today=2024-01-01")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_overlapping_rules() {
+    let repo = create_repo(
+        "due_in = 7\nteam = \"platform\"\n",
+        &[(
+            "main.rs",
+            "// TODO: 2024-01-20 @alice Support more formats\n",
+        )],
+    );
+    fs::create_dir(repo.path().join("src/sub")).unwrap();
+    fs::write(
+        repo.path().join("src/sub/lib.rs"),
+        "// TODO: 2023-12-01 @bob Cache the build\n\
+         // TODO: 2024-01-20 @bob Clean up the cache\n",
+    )
+    .unwrap();
+    let config = fs::read_to_string(repo.path().join("todo-reminder.toml")).unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "{}\n[[rules]]\npaths = [\"src/sub\"]\nlanguage = \"rust\"\n\
+             due_in = 30\nteam = \"infra\"\n",
+            config
+        ),
+    )
+    .unwrap();
+
+    let run = |format: &str| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .arg(format!("--format={}", format))
+            .args(["--no-tty", "--exit-zero", "--today=2024-01-01"])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Each file is checked once, with the rule with the most specific path
+    let output = run("json");
+    assert!(output.status.success());
+    let warnings: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings: Vec<(&str, &str)> = warnings
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            (
+                warning["file"].as_str().unwrap(),
+                warning["team"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        [("src/sub/lib.rs", "infra"), ("src/sub/lib.rs", "infra")]
+    );
+
+    let output = run("stats");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("3 TODOs in 2 files"), "{}", stdout);
+}
+
+#[test]
+fn test_config_discovery() {
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/main.py"), "# TODO: fix this\n").unwrap();
+    fs::write(
+        project.path().join("pyproject.toml"),
+        "[tool.poetry]\nname = \"example\"\n\n\
+         [[tool.todo-reminder.rules]]\npaths = [\"src\"]\nlanguage = \"python\"\n\n\
+         [tool.todo-reminder.parameters]\n",
+    )
+    .unwrap();
+
+    let run = |dir: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(dir)
+            .arg("--format=json")
+            .arg("--exit-zero")
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&project.path().join("src"));
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(
+        "config_discovery_pyproject",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+
+    // A dedicated config file closer to the current directory takes precedence
+    fs::write(
+        project.path().join("src/.todo-reminder.toml"),
+        "[[rules]]\npaths = [\".\"]\nlanguage = \"python\"\n\n[parameters]\n",
+    )
+    .unwrap();
+    let output = run(&project.path().join("src"));
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(
+        "config_discovery_dedicated_file",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+
+    let crate_dir = tempfile::tempdir().unwrap();
+    fs::create_dir(crate_dir.path().join("src")).unwrap();
+    fs::write(crate_dir.path().join("src/lib.rs"), "// TODO: fix this\n").unwrap();
+    fs::write(
+        crate_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"example\"\n\n\
+         [[package.metadata.todo-reminder.rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+         [package.metadata.todo-reminder.parameters]\n",
+    )
+    .unwrap();
+    let output = run(crate_dir.path());
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(
+        "config_discovery_cargo_metadata",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+
+    // Without a config section, Cargo.toml is skipped
+    fs::write(
+        crate_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"example\"\n",
+    )
+    .unwrap();
+    assert_eq!(run(crate_dir.path()).status.code(), Some(2));
+}
+
+#[test]
+fn test_config_extends_and_includes() {
+    let repo = tempfile::tempdir().unwrap();
+    fs::write(
+        repo.path().join("base.toml"),
+        "[parameters]\ndue_in = 30\n\n[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n",
+    )
+    .unwrap();
+    let team = repo.path().join("team");
+    fs::create_dir_all(team.join("src")).unwrap();
+    fs::create_dir_all(team.join("tools")).unwrap();
+    fs::write(
+        team.join("scripts.toml"),
+        "[[rules]]\npaths = [\"${SCRIPTS_DIR}\"]\nlanguage = \"python\"\n",
+    )
+    .unwrap();
+    fs::write(
+        team.join("todo-reminder.toml"),
+        "extends = \"../base.toml\"\ninclude = [\"scripts.toml\"]\n\n[parameters]\ndue_in = 7\n",
+    )
+    .unwrap();
+    fs::write(
+        team.join("src/main.rs"),
+        "// TODO: 2024-01-05 @alice Support more formats\n\
+         // TODO: 2024-01-20 @alice Handle errors\n",
+    )
+    .unwrap();
+    fs::write(team.join("tools/build.py"), "# TODO: fix this\n").unwrap();
+
+    let run = |config: &str, scripts_dir: Option<&str>| {
+        let mut command = Command::new("cargo");
+        command
+            .arg("run")
+            .arg(team.join(config))
+            .arg("--format=json")
+            .arg("--exit-zero")
+            .arg("--today=2024-01-01")
+            .env_remove("SCRIPTS_DIR");
+        if let Some(scripts_dir) = scripts_dir {
+            command.env("SCRIPTS_DIR", scripts_dir);
+        }
+        command.output().expect("Failed to execute command")
+    };
+
+    let output = run("todo-reminder.toml", Some("tools"));
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+
+    let output = run("todo-reminder.toml", None);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SCRIPTS_DIR"));
+
+    fs::write(team.join("cycle.toml"), "extends = \"cycle.toml\"\n").unwrap();
+    let output = run("cycle.toml", Some("tools"));
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("extends or includes itself"));
+}
+
+#[test]
+fn test_nested_config_fragments() {
+    let repo = create_repo(
+        "",
+        &[(
+            "main.rs",
+            "// TODO: 2024-01-20 @alice Support more formats\n",
+        )],
+    );
+    let write = |path: &str, content: &str| {
+        let path = repo.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write("src/generated/.todo-reminder.toml", "disable = true\n");
+    write("src/generated/api.rs", "// TODO: fix this\n");
+    write(
+        "src/legacy/.todo-reminder.toml",
+        "due_in = 30\nteam = \"legacy\"\nkeywords = [\"HACK\"]\nexclude = [\"vendor\"]\n",
+    );
+    write(
+        "src/legacy/mod.rs",
+        "// TODO: fix this\n// HACK: fix this\n// HACK: 2024-01-20 @bob Remove the workaround\n",
+    );
+    write("src/legacy/vendor/lib.rs", "// HACK: fix this\n");
+    write(
+        "src/legacy/parser/.todo-reminder.toml",
+        "check_format_only = true\n",
+    );
+    write(
+        "src/legacy/parser/mod.rs",
+        "// HACK: fix this\n// HACK: 2024-01-20 @carol Handle errors\n",
+    );
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=json")
+        .arg("--exit-zero")
+        .arg("--today=2024-01-01")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_validate_config() {
+    let repo = create_repo(
+        "edtor_url = \"vscode://file/%%path%%\"\ndue_in = \"7\"\n\n\
+         [[rules]]\npaths = [\"src\", \"lib\"]\nlangauge = \"rust\"\nfile_extensions = [\".rs\"]\n\n\
+         [[rules]]\npaths = [\"src\"]\nlanguage = \"rsut\"\n\n\
+         [severity]\noverdue = \"fatal\"\n",
+        &[],
+    );
+    fs::write(
+        repo.path().join("base.toml"),
+        "[parameters]\neditor_url = \"idea://open?file=%%path%%\"\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(repo.path())
+            .arg("validate-config")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+
+    // Unknown keys also fail regular runs
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .current_dir(repo.path())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(2));
+
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        "extends = \"base.toml\"\n\n[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n",
+    )
+    .unwrap();
+    fs::write(repo.path().join("src/.todo-reminder.toml"), "due_inn = 3\n").unwrap();
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<&str>>(),
+        [
+            "base.toml:2:14: Unknown placeholder \"%%path%%\" in parameters.editor_url",
+            "base.toml:2:14: parameters.editor_url has no %%file%% placeholder",
+            "Found 2 problems in todo-reminder.toml",
+        ]
+    );
+
+    fs::write(repo.path().join("base.toml"), "[parameters]\n").unwrap();
+    let output = run(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<&str>>(),
+        [
+            "src/.todo-reminder.toml:1:1: Unknown key \"due_inn\", did you mean \"due_in\"?",
+            "Found 1 problem in todo-reminder.toml",
+        ]
+    );
+
+    fs::remove_file(repo.path().join("src/.todo-reminder.toml")).unwrap();
+    let output = run(&[]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_validate_example_configs() {
+    for config in ["config.example.toml", "tests/test_files/config.toml"] {
+        let output = Command::new("cargo")
+            .arg("run")
+            .arg("validate-config")
+            .arg(format!("--config={}", config))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success(), "{} is invalid", config);
+    }
+}
+
+#[test]
+fn test_json_schema_is_up_to_date() {
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg("validate-config")
+        .arg("--schema")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        fs::read_to_string("todo-reminder.schema.json").unwrap(),
+        "Run `cargo run validate-config --schema > todo-reminder.schema.json`"
+    );
+}
+
+#[test]
+fn test_init() {
+    let repo = tempfile::tempdir().unwrap();
+    for (path, content) in [
+        ("src/main.rs", "// TODO: fix this\n"),
+        ("src/lib.rs", ""),
+        ("tests/cli.rs", ""),
+        ("web/app.ts", "// TODO: 2024-01-01 @alice Handle errors\n"),
+        ("web/node_modules/left-pad/index.js", "// TODO: fix this\n"),
+        ("scripts/build.py", ""),
+        ("target/debug/build.rs", ""),
+        (".git/hooks/pre-commit.sh", ""),
+        ("build.sh", ""),
+        ("README.md", ""),
+    ] {
+        let path = repo.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(repo.path())
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&["init"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Created todo-reminder.toml with 3 rules for rust, python, typescript\n"
+    );
+    let config = fs::read_to_string(repo.path().join("todo-reminder.toml")).unwrap();
+    insta::assert_snapshot!(config);
+
+    assert!(run(&["validate-config"]).status.success());
+    let output = run(&["--format=json", "--today=2024-02-01", "--exit-zero"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("src/main.rs"));
+    assert!(stdout.contains("web/app.ts"));
+    assert!(!stdout.contains("node_modules"));
+
+    // An existing config is only overwritten with --force
+    assert_eq!(run(&["init"]).status.code(), Some(2));
+    assert!(run(&["init", "--force"]).status.success());
+}
+
+#[test]
+fn test_stats_format() {
+    let repo = create_repo(
+        "",
+        &[
+            (
+                "main.rs",
+                "fn main() {}\n\
+                 // TODO: fix this\n\
+                 // TODO: 2024-01-10 @alice Support more formats\n\
+                 // TODO: 2024-03-01 @alice Handle errors\n",
+            ),
+            ("lib.rs", "// TODO: 2024-03-15 @bob Document the API\n"),
+        ],
+    );
+    fs::create_dir(repo.path().join("scripts")).unwrap();
+    fs::write(
+        repo.path().join("scripts/build.py"),
+        "import os\n\n# TODO: 2023-12-01 @bob Cache the build\n\nos.exit(0)\n",
+    )
+    .unwrap();
+    let config = fs::read_to_string(repo.path().join("todo-reminder.toml")).unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "{}\n[[rules]]\npaths = [\"scripts\"]\nlanguage = \"python\"\n",
+            config
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .arg("run")
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=stats")
+        .arg("--no-tty")
+        .arg("--exit-zero")
+        .arg("--today=2024-02-01")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(stdout.lines().collect::<Vec<&str>>());
+
+    // The stats only cover the TODOs of the warnings that pass the filters
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=stats")
+        .arg("--no-tty")
+        .arg("--exit-zero")
+        .arg("--today=2024-02-01")
+        .arg("--owner=bob")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    insta::assert_debug_snapshot!(
+        "stats_format_with_owner_filter",
+        stdout.lines().collect::<Vec<&str>>()
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .arg("--format=stats")
+        .arg("--no-tty")
+        .arg("--today=2024-02-01")
+        .arg("--owner=nobody")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("0 TODOs in 3 files"), "{}", stdout);
+}
+
+#[test]
+fn test_group_and_sort() {
+    let repo = create_repo(
+        "",
+        &[
+            (
+                "main.rs",
+                "// TODO: 2024-03-01 @bob Handle errors\n\
+                 // TODO: fix this\n\
+                 // TODO: 2024-01-10 @alice Support more formats\n",
+            ),
+            (
+                "lib.rs",
+                "// TODO: 2024-01-20 @bob Document the API\n\
+                 // TODO: 2024-02-03 @alice Add examples\n",
+            ),
+        ],
+    );
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args([
+                "--no-tty",
+                "--exit-zero",
+                "--today=2024-02-01",
+                "--due-in=7",
+            ])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let by_owner = run(&["--group-by=owner", "--sort-by=due"]);
+    insta::assert_debug_snapshot!("group_by_owner", by_owner.lines().collect::<Vec<&str>>());
+
+    let by_type = run(&["--group-by=type", "--sort-by=owner"]);
+    insta::assert_debug_snapshot!("group_by_type", by_type.lines().collect::<Vec<&str>>());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&run(&["--format=json", "--group-by=none", "--sort-by=due"])).unwrap();
+    let order: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| format!("{}:{}", warning["file"], warning["line"]))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            "\"src/main.rs\":3",
+            "\"src/lib.rs\":1",
+            "\"src/lib.rs\":2",
+            "\"src/main.rs\":2"
+        ]
+    );
+}
+
+#[test]
+fn test_warning_filters() {
+    let repo = create_repo(
+        "\n[owner_aliases]\ncarol = [\"Carol.Smith@example.com\"]\n",
+        &[
+            (
+                "main.rs",
+                "// TODO: 2024-01-10 @alice Support more formats\n\
+                 // TODO: fix this\n\
+                 // TODO: 2024-02-03 @carol Handle errors\n",
+            ),
+            ("lib.rs", "// TODO: 2024-01-20 @bob Document the API\n"),
+        ],
+    );
+    fs::create_dir(repo.path().join("src/billing")).unwrap();
+    fs::write(
+        repo.path().join("src/billing/invoice.rs"),
+        "// TODO: 2024-01-05 @carol Round the totals\n",
+    )
+    .unwrap();
+    git(
+        repo.path(),
+        &["config", "user.email", "carol.smith@example.com"],
+    );
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(["--format=json", "--today=2024-02-01", "--due-in=7"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let warnings: Vec<String> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|warning| format!("{}:{}", warning["file"].as_str().unwrap(), warning["line"]))
+            .collect();
+        (output.status.code(), warnings)
+    };
+
+    assert_eq!(
+        run(&["--owner", "alice", "--owner=@bob"]),
+        (Some(1), vec!["src/lib.rs:1".into(), "src/main.rs:1".into()])
+    );
+    assert_eq!(
+        run(&["--type", "due-soon,invalid-format"]),
+        (
+            Some(1),
+            vec!["src/main.rs:2".into(), "src/main.rs:3".into()]
+        )
+    );
+    assert_eq!(
+        run(&["--path-prefix", "src/billing"]),
+        (Some(1), vec!["src/billing/invoice.rs:1".into()])
+    );
+    assert_eq!(
+        run(&["--mine", "--type=overdue"]),
+        (Some(1), vec!["src/billing/invoice.rs:1".into()])
+    );
+    // Nothing left after filtering, so the run passes
+    assert_eq!(
+        run(&["--mine", "--path-prefix=src/lib.rs"]),
+        (Some(0), vec![])
+    );
+}
+
+#[test]
+fn test_filter_expressions_and_views() {
+    let repo = create_repo(
+        "\n[views.release-blockers]\nfilter = 'type == \"Overdue\" && days_overdue > 14'\n",
+        &[
+            (
+                "main.rs",
+                "// TODO: 2024-01-10 @alice Support more formats\n\
+                 // TODO: fix this\n\
+                 // TODO: 2024-01-25 @bob Handle errors\n",
+            ),
+            ("lib.rs", "// TODO: 2024-01-05 @carol Document the API\n"),
+        ],
+    );
+    fs::create_dir(repo.path().join("src/api")).unwrap();
+    fs::write(
+        repo.path().join("src/api/routes.rs"),
+        "// TODO: 2024-01-01 @bob Add pagination\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(["--format=json", "--today=2024-02-01"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|warning| format!("{}:{}", warning["file"].as_str().unwrap(), warning["line"]))
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        run(&[
+            "--filter",
+            r#"type == "Overdue" && owner in ["alice", "bob"] && days_overdue > 14 && path ~ "^src/api""#
+        ]),
+        ["src/api/routes.rs:1"]
+    );
+    assert_eq!(
+        run(&["--view", "release-blockers"]),
+        ["src/api/routes.rs:1", "src/lib.rs:1", "src/main.rs:1"]
+    );
+    assert_eq!(
+        run(&["--view=release-blockers", "--filter", "!(owner == \"bob\")"]),
+        ["src/lib.rs:1", "src/main.rs:1"]
+    );
+    assert_eq!(
+        run(&["--filter", "!owner || due_date >= \"2024-01-20\""]),
+        ["src/main.rs:2", "src/main.rs:3"]
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--filter", "ownr == \"bob\""])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Invalid --filter: Unknown field \"ownr\" at column 1, did you mean \"owner\"?"));
+}
+
+#[test]
+fn test_fix_invalid_format() {
+    let repo = create_repo(
+        "",
+        &[(
+            "main.rs",
+            "fn main() {\n\
+             \x20   // TODO fix this\n\
+             \x20   let x = 1; // TODO(alice): handle overflow\n\
+             \x20   /* FIXME - 2024-05-01 clean up */\n\
+             \x20   // Remember the todo list\n\
+             \x20   // TODO: 2024-01-01 @bob Already fine\n\
+             }\n",
+        )],
+    );
+    fs::write(repo.path().join("src/new.rs"), "// todo not committed\n").unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(["--today=2024-02-01", "--no-tty"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&["--fix", "--dry-run", "--default-due=+4w"]);
+    assert!(output.status.success());
+    insta::assert_snapshot!(
+        "fix_dry_run",
+        format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(repo.path().join("src/new.rs")).unwrap(),
+        "// todo not committed\n"
+    );
+
+    let output = run(&["--fix", "--default-owner=@carol"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Skipped src/main.rs:5: The TODO keyword isn't at the start of the comment\n\
+         Fixed 4 TODOs in 2 files\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo.path().join("src/new.rs")).unwrap(),
+        "// TODO: 2024-05-01 @carol not committed\n"
+    );
+
+    // Only the TODO in the text of a comment is left with an invalid format
+    let output = run(&["--format=json", "--exit-zero", "--type=invalid-format"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["line"], 5);
+}
+
+#[test]
+fn test_fix_invalid_dates_and_bare_owners() {
+    let repo = create_repo(
+        "",
+        &[(
+            "main.rs",
+            "// todo 2024/05/01 bob: thing\n\
+             // FIXME 2024-13-45 @x bad date\n",
+        )],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--fix", "--dry-run", "--default-owner=@carol"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("+// TODO: 2024-05-01 @bob thing\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\n // FIXME 2024-13-45 @x bad date\n"),
+        "{}",
+        stdout
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Skipped src/main.rs:2: "), "{}", stderr);
+    assert!(
+        stderr.ends_with("Would fix 1 TODO in 1 file\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_reassign_and_postpone() {
+    let repo = create_repo(
+        "",
+        &[
+            (
+                "main.rs",
+                "// TODO: 2024-01-10 @alice Support more formats\n\
+                 // TODO: 2024-03-01 @bob Handle errors\n\
+                 /* TODO: 2024-01-20 @alice Document the API */\n",
+            ),
+            ("lib.rs", "// TODO: 2024-02-15 @alice Add examples\n"),
+        ],
+    );
+    fs::create_dir(repo.path().join("src/legacy")).unwrap();
+    fs::write(
+        repo.path().join("src/legacy/old.rs"),
+        "// TODO: 2023-12-01 @alice Remove\n",
+    )
+    .unwrap();
+    // A second rule for the same files doesn't change them twice
+    let config_path = repo.path().join("todo-reminder.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    fs::write(
+        &config_path,
+        format!(
+            "[[rules]]\npaths = [\"src/legacy\"]\nlanguage = \"rust\"\n\n{}",
+            config
+        ),
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(repo.path())
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        (
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    };
+    let read = |name: &str| fs::read_to_string(repo.path().join("src").join(name)).unwrap();
+
+    let (patch, summary) = run(&[
+        "reassign",
+        "--from=alice",
+        "--to=@carol",
+        "--path-prefix=src/legacy",
+        "--dry-run",
+    ]);
+    insta::assert_snapshot!("reassign_dry_run", format!("{}\n{}", patch, summary));
+    assert_eq!(read("legacy/old.rs"), "// TODO: 2023-12-01 @alice Remove\n");
+
+    let (summary, _) = run(&["reassign", "--from", "alice", "--to", "carol"]);
+    assert_eq!(
+        summary,
+        "src/legacy/old.rs:1: @alice -> @carol\n\
+         src/lib.rs:1: @alice -> @carol\n\
+         src/main.rs:1: @alice -> @carol\n\
+         src/main.rs:3: @alice -> @carol\n\
+         Reassigned 4 TODOs in 3 files\n"
+    );
+
+    let (summary, _) = run(&[
+        "postpone",
+        "--owner=carol",
+        "--by=30d",
+        "--type=overdue",
+        "--today=2024-02-01",
+    ]);
+    assert_eq!(
+        summary,
+        "src/legacy/old.rs:1: 2023-12-01 -> 2023-12-31\n\
+         src/main.rs:1: 2024-01-10 -> 2024-02-09\n\
+         src/main.rs:3: 2024-01-20 -> 2024-02-19\n\
+         Postponed 3 TODOs in 2 files\n"
+    );
+    run(&["postpone", "--to=2024-06-01", "--path-prefix=src/legacy"]);
+
+    // Bad numbers of days are reported, even when they end with a multibyte character
+    for args in [&["postpone", "--by=3ü"][..], &["--default-due=+3ü"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(repo.path())
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: Invalid"));
+    }
+
+    assert_eq!(
+        read("main.rs"),
+        "// TODO: 2024-02-09 @carol Support more formats\n\
+         // TODO: 2024-03-01 @bob Handle errors\n\
+         /* TODO: 2024-02-19 @carol Document the API */\n"
+    );
+    assert_eq!(read("lib.rs"), "// TODO: 2024-02-15 @carol Add examples\n");
+    assert_eq!(read("legacy/old.rs"), "// TODO: 2024-06-01 @carol Remove\n");
+}
+
+#[test]
+fn test_format_hints_and_explain() {
+    let repo = create_repo(
+        "",
+        &[(
+            "main.rs",
+            "// TODO 2024/05/01 alice fix the parser\n\
+             // TODO(bob): 05-31-2024 clean up\n\
+             /* FIXME: @carol 2024-05-01 remove the fallback */\n\
+             // TODO: 2024-05-01@dave handle errors\n\
+             // TODO: 2024-02-30 @erin fix leap years\n",
+        )],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--today=2024-02-01", "--format=json"])
+        .output()
+        .expect("Failed to execute command");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let hints: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| warning["hint"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        hints,
+        [
+            "Missing colon after TODO. Write the date 2024/05/01 as 2024-05-01. \
+             Missing @ before the owner alice. \
+             Did you mean \"// TODO: 2024-05-01 @alice fix the parser\"?",
+            "The google format isn't enabled in the formats setting. \
+             Write the owner as @bob after the date, not in parentheses. \
+             Write the date 05-31-2024 as 2024-05-31. \
+             Did you mean \"// TODO: 2024-05-31 @bob clean up\"?",
+            "The due date must come before the owner. \
+             Did you mean \"/* FIXME: 2024-05-01 @carol remove the fallback */\"?",
+            "Did you mean \"// TODO: 2024-05-01 @dave handle errors\"?",
+            "2024-02-30 is not a valid date. \
+             Did you mean \"// TODO: YYYY-MM-DD @erin fix leap years\"?",
+        ]
+    );
+
+    let explain = |comment: &str| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .current_dir(repo.path())
+            .args(["explain", comment])
+            .output()
+            .expect("Failed to execute command")
+    };
+    let output = explain("// TODO: 2024-05-01 @alice fix the parser");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Format:      valid (standard)\n\
+         Keyword:     TODO\n\
+         Due date:    2024-05-01\n\
+         Owner:       @alice\n\
+         Description: fix the parser\n"
+    );
+
+    let output = explain("# todo 2024/05/01 alice");
+    assert_eq!(output.status.code(), Some(1));
+    insta::assert_snapshot!("explain_invalid", String::from_utf8_lossy(&output.stdout));
+
+    // A date that doesn't exist makes the format invalid rather than the TODO never due
+    let output = explain("// TODO: 9999-99-99 @bob");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Format:      invalid\n"));
+    assert!(stdout.contains("  - 9999-99-99 is not a valid date\n"));
+
+    let output = explain("// nothing to do here");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Not a TODO: none of TODO, FIXME found\n"
+    );
+
+    // The keywords of the config file apply unless --keyword overrides them
+    let config_path = repo.path().join("todo-reminder.toml");
+    let config = fs::read_to_string(&config_path).unwrap();
+    fs::write(&config_path, format!("{}keywords = [\"HACK\"]\n", config)).unwrap();
+    let output = explain("// HACK: 2024-05-01 @alice fix the parser");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Format:      valid"));
+    let output = explain("// TODO: 2024-05-01 @alice fix the parser");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Not a TODO: none of HACK found\n"
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .current_dir(repo.path())
+        .args(["explain", "// TODO: 2024-05-01 @alice fix the parser"])
+        .arg("--keyword=TODO")
+        .output()
+        .expect("Failed to execute command");
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Format:      valid"));
+}
+
+#[test]
+fn test_alternative_todo_formats() {
+    let repo = create_repo(
+        "formats = [\"standard\", \"doc-tag\", \"google\", \"bracketed\", \"owner-date\"]\n",
+        &[(
+            "main.rs",
+            "// TODO: 2024-01-10 @alice Standard\n\
+             /** @todo 2024-01-11 @bob Doc tag */\n\
+             // TODO(carol): 2024-01-12 Google with a date\n\
+             // TODO(dave): Google without a date\n\
+             // TODO [2024-01-14] (erin) Bracketed\n\
+             // FIXME(frank, 2024-01-15): Owner and date\n\
+             // TODO(grace) Missing colon\n\
+             // TODO(ivan): 2000 rows max per page\n",
+        )],
+    );
+    fs::create_dir(repo.path().join("src/legacy")).unwrap();
+    fs::write(
+        repo.path().join("src/legacy/.todo-reminder.toml"),
+        "formats = [\"standard\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("src/legacy/old.rs"),
+        "// TODO(heidi): 2024-01-16 Not enabled here\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--today=2024-02-01", "--format=json"])
+        .output()
+        .expect("Failed to execute command");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            format!(
+                "{}:{} {} {} {}",
+                warning["file"].as_str().unwrap(),
+                warning["line"],
+                warning["type"].as_str().unwrap(),
+                warning["due_date"].as_str().unwrap_or("-"),
+                warning["owner"].as_str().unwrap_or("-"),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "src/legacy/old.rs:1 InvalidFormat - -",
+            "src/main.rs:1 Overdue 2024-01-10 alice",
+            "src/main.rs:2 Overdue 2024-01-11 bob",
+            "src/main.rs:3 Overdue 2024-01-12 carol",
+            "src/main.rs:5 Overdue 2024-01-14 erin",
+            "src/main.rs:6 Overdue 2024-01-15 frank",
+            "src/main.rs:7 InvalidFormat - -",
+        ]
+    );
+    assert!(json[0]["hint"]
+        .as_str()
+        .unwrap()
+        .starts_with("The google format isn't enabled in the formats setting."));
+
+    // The date is optional in Google style TODOs
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args([
+            "explain",
+            "--todo-format=google",
+            "// TODO(dave): Handle errors",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Format:      valid (google)\n\
+         Keyword:     TODO\n\
+         Due date:    -\n\
+         Owner:       @dave\n\
+         Description: Handle errors\n"
+    );
+}
+
+#[test]
+fn test_partial_due_dates() {
+    let repo = create_repo(
+        "due_in = 14\n",
+        &[(
+            "main.rs",
+            "// TODO: 2024-Q1 @team Plan the migration\n\
+             // TODO: 2024-01 @bob Rotate the keys\n\
+             // TODO: 2024-W05 @carol Update the docs\n\
+             // TODO: 2023 @dave Drop the old API\n\
+             // TODO: 2024-02 @erin Not due yet\n",
+        )],
+    );
+
+    let run = |today: &str, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .arg(format!("--today={}", today))
+            .arg("--no-tty")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run("2024-02-01", &["--format=json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    // 2024-W05 ends on Sunday, February 4th
+    let warnings: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            format!(
+                "{} {} {}",
+                warning["line"],
+                warning["type"].as_str().unwrap(),
+                warning["due_date"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        ["2 Overdue 2024-01", "3 DueSoon 2024-W05", "4 Overdue 2023",]
+    );
+
+    // Only the TODOs due by the end of March are reported
+    let output = run("2024-03-20", &["--format=json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["due_date"], "2024-Q1");
+    assert_eq!(json[0]["type"], "DueSoon");
+    assert_eq!(json[0]["days_until_due"], 11);
+
+    let output = run("2024-02-01", &["--filter=due_date < \"2024-01-31\""]);
+    insta::assert_snapshot!("partial_due_dates", String::from_utf8_lossy(&output.stdout));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args(["postpone", "--by=30d", "--owner=team", "--owner=bob"])
+        .arg(format!(
+            "--config={}",
+            repo.path().join("todo-reminder.toml").display()
+        ))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "src/main.rs:1: 2024-Q1 -> 2024-Q2\n\
+         src/main.rs:2: 2024-01 -> 2024-03\n\
+         Postponed 2 TODOs in 1 file\n"
+    );
+}
+
+#[test]
+fn test_date_formats() {
+    let repo = create_repo(
+        "date_formats = [\"%d.%m.%Y\", \"%m/%d/%Y\", \"%d/%m/%Y\"]\n",
+        &[(
+            "main.rs",
+            "// TODO: 31.12.2023 @anna European\n\
+             // TODO: 12/31/2023 @bob US\n\
+             // TODO: 05/06/2023 @carol Either\n\
+             // TODO: 2023-Q4 @dave Quarter\n\
+             // TODO: 2023-12-31 @erin ISO\n",
+        )],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args([
+            "--today=2024-02-01",
+            "--format=json",
+            "--display-date-format=%d %b %Y",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            format!(
+                "{} {} {}",
+                warning["line"],
+                warning["type"].as_str().unwrap(),
+                warning["due_date"]
+                    .as_str()
+                    .or(warning["hint"].as_str())
+                    .unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "1 Overdue 31 Dec 2023",
+            "2 Overdue 31 Dec 2023",
+            "3 InvalidFormat 05/06/2023 is ambiguous, it could be 2023-05-06 or 2023-06-05. \
+             Did you mean \"// TODO: 06.05.2023 @carol Either\"?",
+            "4 Overdue 2023-Q4",
+            "5 InvalidFormat Write the date 2023-12-31 as 31.12.2023. \
+             Did you mean \"// TODO: 31.12.2023 @erin ISO\"?",
+        ]
+    );
+
+    // Postponed dates keep the format they were written in
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args(["postpone", "--by=1d", "--dry-run"])
+        .arg(format!(
+            "--config={}",
+            repo.path().join("todo-reminder.toml").display()
+        ))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "src/main.rs:1: 31.12.2023 -> 01.01.2024\n\
+         src/main.rs:2: 12/31/2023 -> 01/01/2024\n\
+         src/main.rs:4: 2023-Q4 -> 2024-Q1\n\
+         Would postpone 3 TODOs in 1 file\n"
+    );
+
+    fs::write(
+        repo.path().join("src/.todo-reminder.toml"),
+        "date_formats = [\"%d.%m.%Y %H:%M\"]\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args(["validate-config", "--config"])
+        .arg(repo.path().join("todo-reminder.toml"))
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("Invalid date format \"%d.%m.%Y %H:%M\" in date_formats: %H isn't supported"));
+}
+
+#[test]
+fn test_calendar_due_dates() {
+    let repo = create_repo(
+        "due_in = 7\ncalendar = \"calendar.toml\"\n",
+        &[(
+            "main.rs",
+            "// TODO: sprint-42 @bob Finish the migration\n\
+             // TODO: milestone:v3.0 @team Remove the old API\n\
+             // TODO: sprint-43 @carol Not due yet\n\
+             // TODO: sprint-24 @dave Typo\n",
+        )],
+    );
+    fs::write(
+        repo.path().join("calendar.toml"),
+        "[sprints]\n42 = 2024-03-15\n43 = \"2024-03-29\"\n\n[milestones]\n\"v3.0\" = 2024-03-20\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(["--today=2024-03-18", "--no-tty"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&["--format=json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            format!(
+                "{} {} {} {}",
+                warning["line"],
+                warning["type"].as_str().unwrap(),
+                warning["due_name"].as_str().unwrap(),
+                warning["due_date"]
+                    .as_str()
+                    .or(warning["message"].as_str())
+                    .unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "1 Overdue sprint-42 2024-03-15",
+            "2 DueSoon milestone:v3.0 2024-03-20",
+            "4 UnknownDueDate sprint-24 sprint-24 isn't in the calendar calendar.toml, \
+             did you mean \"sprint-42\"?",
+        ]
+    );
+
+    let output = run(&["--type=overdue"]);
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("2024-03-15 (sprint-42, overdue by 3 days)"));
+
+    // Moving a TODO to a later sprint is a postponement
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "-q", "-m", "calendar"]);
+    let main = fs::read_to_string(repo.path().join("src/main.rs")).unwrap();
+    fs::write(
+        repo.path().join("src/main.rs"),
+        main.replace("sprint-42 @bob", "sprint-43 @bob"),
+    )
+    .unwrap();
+    git(repo.path(), &["commit", "-q", "-am", "postpone"]);
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .current_dir(repo.path())
+        .args(["diff", "HEAD~1", "HEAD", "--format=json"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let changes = json["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["type"], "Modified");
+    assert_eq!(changes[0]["postponed"], true);
+    assert_eq!(changes[0]["old"]["due_date"], "sprint-42");
+    assert_eq!(changes[0]["new"]["due_date"], "sprint-43");
+    git(repo.path(), &["reset", "-q", "--hard", "HEAD~1"]);
+
+    // CSV calendars name the sprints and milestones in full
+    fs::write(
+        repo.path().join("calendar.csv"),
+        "name,start,end\nsprint-24,2024-03-04,2024-03-17\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+         [parameters]\ncalendar = \"calendar.csv\"\n",
+    )
+    .unwrap();
+    let output = run(&["--format=json", "--type=overdue,unknown-due-date"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let types: Vec<(&str, &str)> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            (
+                warning["type"].as_str().unwrap(),
+                warning["due_name"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        types,
+        [
+            ("UnknownDueDate", "sprint-42"),
+            ("UnknownDueDate", "milestone:v3.0"),
+            ("UnknownDueDate", "sprint-43"),
+            ("Overdue", "sprint-24"),
+        ]
+    );
+
+    // The calendar path can use environment variables
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+         [parameters]\ncalendar = \"${TODO_CALENDAR}.csv\"\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--today=2024-03-18", "--format=json", "--type=overdue"])
+        .env("TODO_CALENDAR", "calendar")
+        .output()
+        .expect("Failed to execute command");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[0]["due_name"], "sprint-24");
+
+    // Postponing to a date replaces the sprint
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .current_dir(repo.path())
+        .args([
+            "postpone",
+            "--to=2024-04-05",
+            "--type=overdue",
+            "--today=2024-03-18",
+        ])
+        .env("TODO_CALENDAR", "calendar")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "src/main.rs:4: sprint-24 -> 2024-04-05\n\
+         Postponed 1 TODO in 1 file\n"
+    );
+    assert!(fs::read_to_string(repo.path().join("src/main.rs"))
+        .unwrap()
+        .ends_with("// TODO: 2024-04-05 @dave Typo\n"));
+}
+
+#[test]
+fn test_due_dates_in_daylight_saving_gaps() {
+    // Clocks went from 00:00 to 01:00 on the spring-forward day, so midnight didn't exist
+    let repo = create_repo(
+        "timezone = \"America/Sao_Paulo\"\n",
+        &[(
+            "main.rs",
+            "// TODO: 2018-11-04 @alice Due at the skipped midnight\n\
+             // TODO: 2018-11-04T00:30 @bob Due in the skipped hour\n\
+             // TODO: 2018-11-06 @carol Not due yet\n",
+        )],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .arg(repo.path().join("todo-reminder.toml"))
+        .args(["--format=json", "--today=2018-11-05", "--due-in=0"])
+        .output()
+        .expect("Failed to execute command");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let warnings: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            format!(
+                "{} {}",
+                warning["type"].as_str().unwrap(),
+                warning["owner"].as_str().unwrap()
+            )
+        })
+        .collect();
+    assert_eq!(warnings, ["Overdue alice", "Overdue bob"]);
+}
+
+#[test]
+fn test_timezone_and_due_at() {
+    // The current day far east of UTC, which is what due dates are compared against
+    let today = chrono::Utc::now()
+        .with_timezone(&chrono_tz::Pacific::Kiritimati)
+        .date_naive();
+    let repo = create_repo(
+        "due_in = 1\ntimezone = \"Pacific/Kiritimati\"\ndue_at = \"end_of_day\"\n",
+        &[(
+            "main.rs",
+            &format!(
+                "// TODO: {today} @alice Due today\n\
+                 // TODO: {yesterday} @bob Due yesterday\n\
+                 // TODO: {today}T00:00 @carol Due at midnight\n",
+                today = today,
+                yesterday = today.pred_opt().unwrap(),
+            ),
+        )],
+    );
+
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .arg("--format=json")
+            .output()
+            .expect("Failed to execute command");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|warning| {
+                format!(
+                    "{} {}",
+                    warning["type"].as_str().unwrap(),
+                    warning["owner"].as_str().unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(), ["DueSoon alice", "Overdue bob", "Overdue carol"]);
+
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+         [parameters]\ntimezone = \"Pacific/Kiritimati\"\n",
+    )
+    .unwrap();
+    assert_eq!(run(), ["Overdue alice", "Overdue bob", "Overdue carol"]);
+
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+         [parameters]\ntimezone = \"Europe/Berln\"\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args(["validate-config", "--config"])
+        .arg(repo.path().join("todo-reminder.toml"))
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("Unknown timezone \"Europe/Berln\", use an IANA name like \"Europe/Berlin\""));
+}
+
+#[test]
+fn test_business_days() {
+    let repo = create_repo(
+        "due_in = 1\n",
+        &[(
+            "main.rs",
+            "// TODO: 2024-03-04 @alice Due Monday\n\
+             // TODO: 2024-03-05 @bob Due Tuesday\n\
+             // TODO: 2024-03-06 @carol Due Wednesday\n",
+        )],
+    );
+    let config = "[[rules]]\npaths = [\"src\"]\nlanguage = \"rust\"\n\n\
+                  [parameters]\ndue_in = 1\n\n[business_days]\n";
+
+    // 2024-03-01 is a Friday
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+            .arg(repo.path().join("todo-reminder.toml"))
+            .args(["--today=2024-03-01", "--format=json"])
+            .output()
+            .expect("Failed to execute command");
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|warning| {
+                format!(
+                    "{} {} {}",
+                    warning["owner"].as_str().unwrap(),
+                    warning["days_until_due"],
+                    warning["business_days_until_due"]
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(run().is_empty());
+
+    fs::write(repo.path().join("todo-reminder.toml"), config).unwrap();
+    assert_eq!(run(), ["alice 3 1"]);
+
+    // Holidays don't count either, whether inline or from a file
+    fs::write(
+        repo.path().join("holidays.ics"),
+        "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20240305\nSUMMARY:Holiday\n\
+         END:VEVENT\nEND:VCALENDAR\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "{}holidays = [\"2024-03-04\"]\nholidays_file = \"holidays.ics\"\n",
+            config
+        ),
+    )
+    .unwrap();
+    assert_eq!(run(), ["alice 3 0", "bob 4 0", "carol 5 1"]);
+
+    // Due dates far away are counted without walking every day
+    fs::write(
+        repo.path().join("src/main.rs"),
+        "// TODO: 2025-03-03 @dave A year away\n\
+         // TODO: 9999-12-31 @erin Far away\n",
+    )
+    .unwrap();
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!(
+            "{}holidays = [\"2024-03-04\", \"2024-03-09\"]\n",
+            config.replace("due_in = 1", "due_in = 300")
+        ),
+    )
+    .unwrap();
+    assert_eq!(run(), ["dave 367 260"]);
+
+    fs::write(
+        repo.path().join("todo-reminder.toml"),
+        format!("{}weekend = [\"sat\", \"sunday\", \"sonntag\"]\n", config),
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_todo-reminder"))
+        .args(["validate-config", "--config"])
+        .arg(repo.path().join("todo-reminder.toml"))
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("Unknown weekend day \"sonntag\", use mon, tue, wed, thu, fri, sat or sun"));
+}